@@ -0,0 +1,253 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use gtk::pango;
+
+use crate::draw::{Corner, OverlayKind};
+use crate::export::{self, ImageSize, PageSetup, Paper};
+use crate::generator::{self, names::{NameList, NameSource}, placement::Shape};
+use crate::map::Map;
+use crate::palette::Palette;
+use crate::project;
+use crate::theme;
+
+const RENDER_USAGE: &str = "\
+usage: starmap render [options] [-o <file.png|file.svg|file.pdf>] [--catalogue <file.csv|file.json>]
+
+Renders a map without opening a window.
+
+options:
+  --project <file>        start from a saved project instead of a new map, names and
+                          shape options generate its stars again from its seed
+  --seed <hex>            seed to generate the stars from (random by default)
+  --count <n>             number of stars to draw (default 32)
+  --names <source>        catalogue for real star names, generated for made up ones
+  --name-list <file>      take star names from a text file with a name per line, or a JSON array
+  --extend-names          add the names from --name-list to the catalogue instead of replacing it
+  --shape <shape>         uniform, spiral, elliptical, globular, clusters or band (default uniform)
+  --width <px>            image width (default 1920)
+  --height <px>           image height (default 1080)
+  --dpi <dpi>             resolution to draw at, 96 looks like the window (default 96)
+  --theme <name>          dark, light or a saved theme (default dark)
+  --palette <name>        star class colors: realistic, high-contrast, colorblind-safe or grayscale
+  --font <description>    font for the star names, e.g. \"Monospace Bold 12\"
+  --jumpdistance <ly>     maximum length of a jumpline (default 10)
+  --no-jumplines          don't draw jumplines
+  --display-class         show the star class next to its name
+  --spectral-type         show the full spectral type, like G2V, next to the name
+  --glow                  draw a glow around bright O, B and A stars
+  --monochrome            draw classes as glyphs in one color, with a legend
+  --title <text>          name of the map, for the title overlay
+  --overlay <name>[:<corner>]  show title, legend, scale-bar or compass, optionally in
+                          the top-left, top-right, bottom-left or bottom-right corner
+  -o, --output <file>     where to write the map, as png, svg or pdf
+  --catalogue <file>      where to write the list of stars, as csv or json
+
+shape options, lengths are fractions of the map:
+  --arms <n>              spiral arms (default 2)
+  --twist <turns>         how far the arms wind around (default 0.75)
+  --spread <length>       how far stars stray from arms and clusters (default 0.02)
+  --flattening <0-1>      how flat an elliptical galaxy is (default 0.4)
+  --angle <degrees>       rotation of an elliptical galaxy or band (default 30)
+  --core <length>         core radius of a globular cluster (default 0.05)
+  --clusters <n>          number of open clusters (default 5)
+  --band-width <length>   width of the band (default 0.12)
+  --rift <0-1>            width of the rift in the band (default 0.5)
+  --separation <ly>       minimum distance between stars, for any shape (default 0)
+
+pdf options:
+  --paper <size>          a4, a3, letter or tabloid (default a4)
+  --portrait              print in portrait instead of landscape
+  --margin <mm>           blank border around the map (default 10)
+  --bleed <mm>            extra background past the edge of the page (default 0)
+  --pages <columns>x<rows>  spread the map over several pages (default 1x1)
+  --overlap <mm>          how much neighbouring pages share (default 10)
+";
+
+/// Entry point for `starmap render`.
+pub fn render(args: &[String]) -> Result<(), String> {
+    let mut seed = None;
+    let mut size = ImageSize::default();
+    let mut output = None;
+    let mut catalogue = None;
+    let mut extend_names = false;
+    let mut setup = PageSetup::default();
+
+    if args.iter().filter(|arg| *arg == "--project").count() > 1 {
+        return Err("--project can only be given once".into());
+    }
+    // the project is opened first, so the other options apply on top of it wherever they are
+    let (mut map, generated_with) = match args.iter().position(|arg| arg == "--project") {
+        Some(i) => {
+            let path = args.get(i + 1).ok_or("--project requires a value")?;
+            let map = project::open(Path::new(path))?;
+            // names and placement the stars of the project were generated with
+            let generated_with = (map.names.clone(), map.placement);
+            (map, Some(generated_with))
+        },
+        None => (Map::new(Vec::new(), 0), None),
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--project" => {
+                value(&mut args, arg)?;
+            },
+            "--seed" => {
+                let value = value(&mut args, arg)?;
+                seed = Some(generator::parse_seed(value).map_err(|_| format!("invalid seed: {value}"))?);
+            },
+            "--count" => {
+                map.starcount = number(&mut args, arg)?;
+                // the same limit as in the window, beyond it the stars don't fit in memory
+                if map.starcount > generator::MAX_STARS {
+                    return Err(format!("--count can be at most {}", generator::MAX_STARS));
+                }
+            },
+            "--name-list" => map.names = NameSource::Custom(NameList::load(Path::new(value(&mut args, arg)?))?),
+            "--extend-names" => extend_names = true,
+            "--names" => {
+                map.names = match value(&mut args, arg)? {
+                    "catalogue" => NameSource::Catalogue,
+                    "generated" => NameSource::Generated,
+                    other => return Err(format!("unknown name source: {other}")),
+                }
+            },
+            "--shape" => {
+                let value = value(&mut args, arg)?;
+                map.placement.shape = Shape::from_name(value).ok_or_else(|| format!("unknown shape: {value}"))?;
+            },
+            // the same ranges as in the window
+            "--arms" => map.placement.arms = ranged(&mut args, arg, 1.0, 12.0)? as u32,
+            "--twist" => map.placement.twist = ranged(&mut args, arg, 0.0, 3.0)?,
+            "--spread" => map.placement.spread = ranged(&mut args, arg, 0.0, 0.3)?,
+            "--flattening" => map.placement.flattening = ranged(&mut args, arg, 0.0, 0.9)?,
+            "--angle" => map.placement.angle = ranged(&mut args, arg, -180.0, 180.0)?,
+            "--core" => map.placement.core = ranged(&mut args, arg, 0.005, 0.3)?,
+            "--clusters" => map.placement.clusters = ranged(&mut args, arg, 1.0, 50.0)? as u32,
+            "--band-width" => map.placement.width = ranged(&mut args, arg, 0.01, 0.5)?,
+            "--rift" => map.placement.rift = ranged(&mut args, arg, 0.0, 1.0)?,
+            "--separation" => map.placement.separation = ranged(&mut args, arg, 0.0, 20.0)?,
+            "--width" => size.width = number(&mut args, arg)?,
+            "--height" => size.height = number(&mut args, arg)?,
+            "--dpi" => size.dpi = number(&mut args, arg)?,
+            "--theme" => {
+                let name = value(&mut args, arg)?;
+                map.colors = match theme::find(&theme::load_all(), name) {
+                    Some(theme) => theme.colors.clone(),
+                    None => return Err(format!("unknown theme: {name}")),
+                }
+            },
+            "--palette" => {
+                let name = value(&mut args, arg)?;
+                map.colors.classes = Palette::from_name(name).ok_or_else(|| format!("unknown palette: {name}"))?.colors();
+            },
+            "--font" => map.font_desc = pango::FontDescription::from_string(value(&mut args, arg)?),
+            "--jumpdistance" => map.jumpdistance = number(&mut args, arg)?,
+            "--no-jumplines" => map.jumplines = false,
+            "--display-class" => map.display_class = true,
+            "--monochrome" => {
+                map.monochrome = true;
+                map.overlays.legend.visible = true;
+            },
+            "--title" => map.title = value(&mut args, arg)?.to_owned(),
+            "--overlay" => {
+                let value = value(&mut args, arg)?;
+                let (name, corner) = match value.split_once(':') {
+                    Some((name, corner)) => (name, Some(corner)),
+                    None => (value, None),
+                };
+                let kind = OverlayKind::from_name(name).ok_or_else(|| format!("unknown overlay: {name}"))?;
+                let overlay = map.overlays.get_mut(kind);
+                overlay.visible = true;
+                if let Some(corner) = corner {
+                    overlay.corner = Corner::from_name(corner).ok_or_else(|| format!("unknown corner: {corner}"))?;
+                }
+            },
+            "--spectral-type" => {
+                map.display_class = true;
+                map.spectral_type = true;
+            },
+            "--glow" => map.glow = true,
+            "--paper" => {
+                let value = value(&mut args, arg)?;
+                setup.paper = Paper::from_name(value).ok_or_else(|| format!("unknown paper size: {value}"))?;
+            },
+            "--portrait" => setup.landscape = false,
+            "--margin" => setup.margin = number(&mut args, arg)?,
+            "--bleed" => setup.bleed = number(&mut args, arg)?,
+            "--pages" => {
+                let value = value(&mut args, arg)?;
+                let (columns, rows) = value.split_once('x')
+                    .and_then(|(columns, rows)| Some((columns.parse().ok()?, rows.parse().ok()?)))
+                    .ok_or_else(|| format!("invalid page grid: {value}"))?;
+                setup.columns = columns;
+                setup.rows = rows;
+            },
+            "--overlap" => setup.overlap = number(&mut args, arg)?,
+            "-o" | "--output" => output = Some(value(&mut args, arg)?.to_owned()),
+            "--catalogue" => catalogue = Some(value(&mut args, arg)?.to_owned()),
+            "-h" | "--help" => {
+                print!("{RENDER_USAGE}");
+                return Ok(());
+            },
+            other => return Err(format!("unknown option: {other}\n\n{RENDER_USAGE}")),
+        }
+    }
+
+    if output.is_none() && catalogue.is_none() {
+        return Err(format!("no output file given\n\n{RENDER_USAGE}"));
+    }
+    if let NameSource::Custom(list) = &mut map.names {
+        list.extend |= extend_names;
+    } else if extend_names {
+        return Err("--extend-names needs a --name-list".into());
+    }
+
+    // a project brings its own stars, unless a seed is given or they are named or placed differently
+    let changed = generated_with.is_some_and(|(names, placement)| names != map.names || placement != map.placement);
+    if seed.is_some() || changed || map.stars.is_empty() {
+        let seed = match seed {
+            Some(seed) => seed,
+            None if changed => map.seed,
+            None => {
+                let seed = rand::random();
+                eprintln!("seed: {seed:#x}");
+                seed
+            },
+        };
+        map.generate(seed);
+    } else {
+        map.set_starcount(map.starcount);
+    }
+    if map.missing_stars() > 0 {
+        eprintln!("warning: only {} stars fit {} ly apart", map.stars.len(), map.placement.separation);
+    }
+
+    if let Some(output) = output {
+        export::export(Path::new(&output), &size, &map, &setup)?;
+    }
+    if let Some(catalogue) = catalogue {
+        export::catalogue::write_catalogue(Path::new(&catalogue), &map)?;
+    }
+    Ok(())
+}
+
+fn value<'a>(args: &mut impl Iterator<Item = &'a String>, flag: &str) -> Result<&'a str, String> {
+    args.next().map(String::as_str).ok_or_else(|| format!("{flag} requires a value"))
+}
+
+/// A number from `min` to `max`, anything else would throw off the placement.
+fn ranged<'a>(args: &mut impl Iterator<Item = &'a String>, flag: &str, min: f64, max: f64) -> Result<f64, String> {
+    let value: f64 = number(args, flag)?;
+    if !(min..=max).contains(&value) {
+        return Err(format!("{flag} has to be from {min} to {max}, not {value}"));
+    }
+    Ok(value)
+}
+
+fn number<'a, T: FromStr>(args: &mut impl Iterator<Item = &'a String>, flag: &str) -> Result<T, String> {
+    let value = value(args, flag)?;
+    value.parse().map_err(|_| format!("invalid value for {flag}: {value}"))
+}
@@ -1,89 +1,212 @@
 use gtk::pango::ffi::PANGO_SCALE;
-use rand_distr::num_traits::Pow;
-use relm4::abstractions::DrawContext;
 
 use std::f64::consts::PI;
 
-use crate::{generator::Star, App};
+use crate::generator::{classes::{StarClass, Symbol}, companions::Companion, Star};
+use crate::map::Map;
 
-pub fn draw(app: &mut crate::App) {
-    let cx: DrawContext = app.draw_handler.get_context();
-    let app: &App = &*app;
+mod glyphs;
+mod labels;
+mod orrery;
+mod overlays;
 
-    let width = app.draw_handler.width();
-    let height = app.draw_handler.height();
+pub use orrery::draw_orrery;
+pub use overlays::{Corner, OverlayKind, Overlays};
 
-    cx.set_source_rgb(app.colors.wall.0, app.colors.wall.1, app.colors.wall.2);
+static ROUTE_COLOR: (f64,f64,f64) = (1.0, 0.6, 0.1);
+
+/// A star name as it would have been painted, for surfaces where names should stay text.
+pub struct Label {
+    pub text: String,
+    pub x: f64,
+    /// Position of the baseline
+    pub y: f64,
+}
+
+pub fn draw(cx: &cairo::Context, width: f64, height: f64, map: &Map) {
+    render(cx, width, height, map, None);
+}
+
+/// Like [`draw`], but returns the star names instead of painting them.
+pub fn draw_without_labels(cx: &cairo::Context, width: f64, height: f64, map: &Map) -> Vec<Label> {
+    let mut labels = Vec::new();
+    render(cx, width, height, map, Some(&mut labels));
+    labels
+}
+
+fn render(cx: &cairo::Context, width: f64, height: f64, map: &Map, labels: Option<&mut Vec<Label>>) {
+    cx.set_source_rgb(map.colors.wall.0, map.colors.wall.1, map.colors.wall.2);
     cx.paint().unwrap();
 
-    if app.jumplines {
-        draw_jumplines(&cx, width, height, app);
+    if map.jumplines {
+        draw_jumplines(cx, width, height, map);
+    }
+    draw_route(cx, width, height, map);
+
+    if map.glow && !map.monochrome {
+        for star in map.visible_stars() {
+            draw_glow(cx, width, height, star, map);
+        }
+    }
+    for star in map.visible_stars() {
+        draw_star(cx, width, height, star, map);
     }
 
-    for star in &app.stars[0..app.starcount as usize] {
-        draw_star(&cx, width, height, star, app);
+    let placed = labels::place_labels(cx, width, height, map);
+    match labels {
+        Some(labels) => labels.extend(placed),
+        None => placed.iter().for_each(|label| draw_label(cx, label, map)),
     }
 
+    overlays::draw_overlays(cx, width, height, map);
 }
 
-fn draw_jumplines(cx: &DrawContext, width: i32, height: i32, app: &App) {
-    for star in &app.stars[0..app.starcount as usize] {
-        cx.set_source_rgb(app.colors.jumplines.0, app.colors.jumplines.1, app.colors.jumplines.2);
-        cx.set_line_width(3.0);
-        for jstar in &app.stars[0..app.starcount as usize] {
-            let distance: f64 = (((star.cords.0 - jstar.cords.0).abs().pow(2) + (star.cords.1- jstar.cords.1).abs().pow(2)) as f64).sqrt();
-            if distance * app.scale < app.jumpdistance {
-                cx.move_to(star.cords.0 * width as f64, star.cords.1 * height as f64);
-                cx.line_to(jstar.cords.0 * width as f64, jstar.cords.1 * height as f64);
-                cx.stroke().unwrap();
-            }
-        }
+/// Rings the star that is selected in the UI.
+pub fn draw_selection(cx: &cairo::Context, width: f64, height: f64, star: &Star, map: &Map) {
+    cx.set_source_rgb(map.colors.starnames.0, map.colors.starnames.1, map.colors.starnames.2);
+    cx.set_line_width(2.0);
+    cx.new_path();
+    cx.arc(star.cords.0 * width, star.cords.1 * height, system_radius(star) + 5.0, 0.0, 2.0 * PI);
+    cx.stroke().unwrap();
+}
+
+/// Highlights the route over the jumplines.
+fn draw_route(cx: &cairo::Context, width: f64, height: f64, map: &Map) {
+    let stars = map.visible_stars();
+    cx.set_source_rgb(ROUTE_COLOR.0, ROUTE_COLOR.1, ROUTE_COLOR.2);
+    cx.set_line_width(5.0);
+    cx.new_path();
+    for &star in &map.route {
+        cx.line_to(stars[star].cords.0 * width, stars[star].cords.1 * height);
+    }
+    cx.stroke().unwrap();
+}
+
+fn draw_jumplines(cx: &cairo::Context, width: f64, height: f64, map: &Map) {
+    cx.set_source_rgb(map.colors.jumplines.0, map.colors.jumplines.1, map.colors.jumplines.2);
+    cx.set_line_width(3.0);
+    let stars = map.visible_stars();
+    for &(a, b) in &map.jumps.edges {
+        cx.move_to(stars[a].cords.0 * width, stars[a].cords.1 * height);
+        cx.line_to(stars[b].cords.0 * width, stars[b].cords.1 * height);
     }
+    cx.stroke().unwrap();
 }
 
-fn draw_star(cx: &DrawContext, width: i32, height: i32, star: &Star, app: &App) {
-    // star shape
-    if let Some(starcolor) = app.colors.starcolor {
-        cx.set_source_rgb(starcolor.0, starcolor.1, starcolor.2);
+fn draw_star(cx: &cairo::Context, width: f64, height: f64, star: &Star, map: &Map) {
+    let (x, y) = (star.cords.0 * width, star.cords.1 * height);
+    let radius = dot_radius(star);
+    draw_body(cx, x, y, radius, star.class, map);
 
-    } else {
-        let color = starclass2color(star.class);
-        cx.set_source_rgb(color.0, color.1, color.2);
+    // companions huddle around the main star, evenly spread from the lower right
+    let count = star.companions.len() as f64;
+    for (i, companion) in star.companions.iter().enumerate() {
+        let small = companion_radius(companion);
+        let angle = PI / 4.0 + i as f64 * 2.0 * PI / count;
+        let distance = radius + small + 1.0;
+        draw_body(cx, x + distance * angle.cos(), y + distance * angle.sin(), small, companion.class, map);
     }
-    cx.arc(star.cords.0 * width as f64, star.cords.1 * height as f64, 4.0, 0.0, 2.0 * PI);
-    cx.fill().unwrap();
+}
 
-    // star name
-    let layout = pangocairo::functions::create_layout(&cx);
-    layout.set_font_description(Some(&app.font_desc));
-    if app.display_class {
-        layout.set_text(&format!("{} [{}]", star.name, star.class));
-    } else {
-        layout.set_text(&star.name);
+/// The symbol of one object of `class`, centered at `x`, `y`.
+/// The color of all stars if there is one, otherwise the color of the class.
+fn star_color(class: StarClass, map: &Map) -> (f64, f64, f64) {
+    map.colors.starcolor.unwrap_or_else(|| map.colors.classes.get(class))
+}
+
+fn draw_body(cx: &cairo::Context, x: f64, y: f64, radius: f64, class: StarClass, map: &Map) {
+    if map.monochrome {
+        glyphs::draw_glyph(cx, x, y, radius, class.glyph(), map);
+        return;
+    }
+    let color = star_color(class, map);
+    cx.set_source_rgb(color.0, color.1, color.2);
+    cx.new_path();
+    match class.symbol() {
+        Symbol::Dot => {
+            cx.arc(x, y, radius, 0.0, 2.0 * PI);
+            cx.fill().unwrap();
+        },
+        Symbol::Pulsar => {
+            cx.arc(x, y, radius, 0.0, 2.0 * PI);
+            cx.fill().unwrap();
+            cx.set_line_width(1.5);
+            cx.move_to(x - 3.0 * radius, y + 3.0 * radius);
+            cx.line_to(x + 3.0 * radius, y - 3.0 * radius);
+            cx.stroke().unwrap();
+        },
+        Symbol::Ring => {
+            cx.set_line_width(2.0);
+            cx.arc(x, y, (radius - 1.0).max(1.0), 0.0, 2.0 * PI);
+            cx.stroke_preserve().unwrap();
+            cx.set_source_rgb(map.colors.wall.0, map.colors.wall.1, map.colors.wall.2);
+            cx.fill().unwrap();
+        },
+        Symbol::Burst => {
+            cx.arc(x, y, radius, 0.0, 2.0 * PI);
+            cx.fill().unwrap();
+            cx.set_line_width(1.5);
+            for (dx, dy) in [(1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), (0.0, -1.0)] {
+                cx.move_to(x + dx * radius, y + dy * radius);
+                cx.line_to(x + dx * radius * 2.0, y + dy * radius * 2.0);
+            }
+            cx.stroke().unwrap();
+        },
     }
-    cx.set_source_rgb(app.colors.starnames.0, app.colors.starnames.1, app.colors.starnames.2);
-    cx.move_to(star.cords.0 * width as f64 + 6.0, star.cords.1 * height as f64 - (layout.size().1 / PANGO_SCALE) as f64);
-    pangocairo::functions::show_layout(&cx, &layout);
 }
 
-/*
-def hex_to_rgb(hex_color)
-    r = hex_color[0..1].to_i(16) / 255.0
-    g = hex_color[2..3].to_i(16) / 255.0
-    b = hex_color[4..5].to_i(16) / 255.0
-    [r.round(2), g.round(2), b.round(2)]
-end
-*/
+/// Radius of the dot of a star in pixels, brighter stars are bigger.
+/// The sun would be 4px, every tenfold of its luminosity adds half a pixel.
+pub fn dot_radius(star: &Star) -> f64 {
+    body_radius(star.class, star.properties.luminosity)
+}
 
-fn starclass2color(class: char) -> (f64,f64,f64) {
+fn body_radius(class: StarClass, luminosity: f64) -> f64 {
     match class {
-        'O' => (0.61, 0.69, 1.0),
-        'B' => (0.64, 0.75, 1.0),
-        'A' => (0.84, 0.88, 1.0),
-        'F' => (0.98, 0.96, 1.0),
-        'G' => (1.0, 0.93, 0.89),
-        'K' => (1.0, 0.85, 0.71),
-        'M' => (1.0, 0.71, 0.42),
-        _ => unreachable!(),
+        // black holes don't shine at all, but should still be seen
+        StarClass::BlackHole => 5.0,
+        _ => (4.0 + 0.5 * luminosity.log10()).clamp(2.5, 8.0),
     }
 }
+
+/// Companions are drawn at half the size they would have on their own.
+fn companion_radius(companion: &Companion) -> f64 {
+    (body_radius(companion.class, companion.properties.luminosity) / 2.0).max(1.5)
+}
+
+/// Radius of a circle around the star and all of its companions.
+pub fn system_radius(star: &Star) -> f64 {
+    let radius = dot_radius(star);
+    star.companions.iter()
+        .map(|companion| radius + 2.0 * companion_radius(companion) + 1.0)
+        .fold(radius, f64::max)
+}
+
+/// A soft halo in the color of the star, only the bright classes get one.
+fn draw_glow(cx: &cairo::Context, width: f64, height: f64, star: &Star, map: &Map) {
+    let strength = match star.class {
+        StarClass::O | StarClass::WolfRayet => 1.0,
+        StarClass::B => 0.8,
+        StarClass::A => 0.5,
+        _ => return,
+    };
+    let (x, y) = (star.cords.0 * width, star.cords.1 * height);
+    let radius = dot_radius(star) * 4.0;
+    let color = star_color(star.class, map);
+
+    let gradient = cairo::RadialGradient::new(x, y, 0.0, x, y, radius);
+    gradient.add_color_stop_rgba(0.0, color.0, color.1, color.2, 0.6 * strength);
+    gradient.add_color_stop_rgba(1.0, color.0, color.1, color.2, 0.0);
+    cx.set_source(&gradient).unwrap();
+    cx.arc(x, y, radius, 0.0, 2.0 * PI);
+    cx.fill().unwrap();
+}
+
+fn draw_label(cx: &cairo::Context, label: &Label, map: &Map) {
+    let layout = pangocairo::functions::create_layout(cx);
+    layout.set_font_description(Some(&map.font_desc));
+    layout.set_text(&label.text);
+    cx.set_source_rgb(map.colors.starnames.0, map.colors.starnames.1, map.colors.starnames.2);
+    cx.move_to(label.x, label.y - (layout.baseline() / PANGO_SCALE) as f64);
+    pangocairo::functions::show_layout(cx, &layout);
+}
@@ -0,0 +1,50 @@
+use std::f64::consts::PI;
+
+use crate::generator::classes::{Fill, Glyph, Outline};
+use crate::map::Map;
+
+/// Draws `glyph` in the color of the star names, hollow glyphs are filled with the background.
+pub fn draw_glyph(cx: &cairo::Context, x: f64, y: f64, radius: f64, glyph: Glyph, map: &Map) {
+    let (ink, wall) = (map.colors.starnames, map.colors.wall);
+    let radius = radius.max(3.0);
+    cx.new_path();
+    outline(cx, x, y, radius, glyph.outline);
+    match glyph.fill {
+        Fill::Solid => {
+            cx.set_source_rgb(ink.0, ink.1, ink.2);
+            cx.fill().unwrap();
+        },
+        Fill::Hollow | Fill::Ringed => {
+            cx.set_source_rgb(wall.0, wall.1, wall.2);
+            cx.fill().unwrap();
+            // inset, so hollow and solid glyphs are the same size
+            outline(cx, x, y, radius - 0.6, glyph.outline);
+            cx.set_source_rgb(ink.0, ink.1, ink.2);
+            cx.set_line_width(1.2);
+            cx.stroke().unwrap();
+        },
+    }
+    if glyph.fill == Fill::Ringed {
+        cx.arc(x, y, radius * 0.3, 0.0, 2.0 * PI);
+        cx.fill().unwrap();
+    }
+}
+
+fn outline(cx: &cairo::Context, x: f64, y: f64, radius: f64, outline: Outline) {
+    // corners on a circle around `cy`, starting at the top
+    let polygon = |cy: f64, corners: usize, radius_of: &dyn Fn(usize) -> f64| {
+        for i in 0..corners {
+            let angle = -PI / 2.0 + i as f64 * 2.0 * PI / corners as f64;
+            cx.line_to(x + radius_of(i) * angle.cos(), cy + radius_of(i) * angle.sin());
+        }
+        cx.close_path();
+    };
+    match outline {
+        Outline::Circle => cx.arc(x, y, radius, 0.0, 2.0 * PI),
+        Outline::Star => polygon(y, 10, &|i| if i % 2 == 0 { radius * 1.2 } else { radius * 0.5 }),
+        Outline::Diamond => polygon(y, 4, &|_| radius * 1.15),
+        Outline::Square => cx.rectangle(x - radius * 0.85, y - radius * 0.85, radius * 1.7, radius * 1.7),
+        // lowered a bit, so it looks centered
+        Outline::Triangle => polygon(y + radius * 0.2, 3, &|_| radius * 1.2),
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+
+use gtk::pango::ffi::PANGO_SCALE;
+
+use crate::map::Map;
+
+use super::{overlays, system_radius, Label};
+
+/// Spots around a star a label is tried at, as the direction from the star
+/// and how far the label is moved by its own size. The first is where names always used to go.
+static CANDIDATES: [((f64, f64), (f64, f64)); 8] = [
+    // right, sitting on the star
+    ((1.0, 0.0), (0.0, -1.0)),
+    // right
+    ((1.0, 0.0), (0.0, -0.5)),
+    // left
+    ((-1.0, 0.0), (-1.0, -0.5)),
+    // above
+    ((0.0, -1.0), (-0.5, -1.0)),
+    // below
+    ((0.0, 1.0), (-0.5, 0.0)),
+    // diagonals
+    ((-1.0, -1.0), (-1.0, -1.0)),
+    ((1.0, 1.0), (0.0, 0.0)),
+    ((-1.0, 1.0), (-1.0, 0.0)),
+];
+
+/// Distance between the dot of a star and its label
+const GAP: f64 = 2.0;
+/// Size of the cells obstacles are sorted into
+const CELL: f64 = 64.0;
+/// Names are never shortened to fewer letters than this
+const SHORTEST: usize = 4;
+
+#[derive(Clone, Copy)]
+struct Rect {
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+}
+
+impl Rect {
+    fn overlaps(&self, other: &Rect) -> bool {
+        self.x0 < other.x1 && other.x0 < self.x1 && self.y0 < other.y1 && other.y0 < self.y1
+    }
+
+    /// Whether the line from `a` to `b` goes through the rectangle, with Liang-Barsky clipping.
+    fn crosses(&self, a: (f64, f64), b: (f64, f64)) -> bool {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let (mut enter, mut leave) = (0.0f64, 1.0f64);
+        for (p, q) in [(-dx, a.0 - self.x0), (dx, self.x1 - a.0), (-dy, a.1 - self.y0), (dy, self.y1 - a.1)] {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return false;
+                }
+            } else if p < 0.0 {
+                enter = enter.max(q / p);
+            } else {
+                leave = leave.min(q / p);
+            }
+        }
+        enter <= leave
+    }
+}
+
+enum Obstacle {
+    Dot(Rect),
+    Label(Rect),
+    /// The title, legend and other overlays drawn over the map
+    Overlay(Rect),
+    Jumpline((f64, f64), (f64, f64)),
+}
+
+/// Everything labels should stay clear of, sorted into a grid so only nearby obstacles are checked.
+#[derive(Default)]
+struct Obstacles {
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    obstacles: Vec<Obstacle>,
+}
+
+impl Obstacles {
+    fn cells(rect: Rect) -> impl Iterator<Item = (i64, i64)> {
+        let (x0, x1) = ((rect.x0 / CELL).floor() as i64, (rect.x1 / CELL).floor() as i64);
+        let (y0, y1) = ((rect.y0 / CELL).floor() as i64, (rect.y1 / CELL).floor() as i64);
+        (x0..=x1).flat_map(move |x| (y0..=y1).map(move |y| (x, y)))
+    }
+
+    /// The cells the line from `a` to `b` passes through, a column of cells at a time.
+    /// Long jumplines would fill most of their bounding box otherwise.
+    fn line_cells(a: (f64, f64), b: (f64, f64)) -> impl Iterator<Item = (i64, i64)> {
+        let (a, b) = if a.0 <= b.0 { (a, b) } else { (b, a) };
+        let (x0, x1) = ((a.0 / CELL).floor() as i64, (b.0 / CELL).floor() as i64);
+        // only needed when the line spans more than one column, so it isn't vertical
+        let y_at = move |x: f64| a.1 + (x - a.0) / (b.0 - a.0) * (b.1 - a.1);
+        (x0..=x1).flat_map(move |x| {
+            let enter = if x == x0 { a.1 } else { y_at(x as f64 * CELL) };
+            let leave = if x == x1 { b.1 } else { y_at((x + 1) as f64 * CELL) };
+            let (y0, y1) = ((enter.min(leave) / CELL).floor() as i64, (enter.max(leave) / CELL).floor() as i64);
+            (y0..=y1).map(move |y| (x, y))
+        })
+    }
+
+    fn add(&mut self, obstacle: Obstacle) {
+        let cells: Vec<(i64, i64)> = match obstacle {
+            Obstacle::Dot(rect) | Obstacle::Label(rect) | Obstacle::Overlay(rect) => Obstacles::cells(rect).collect(),
+            Obstacle::Jumpline(a, b) => Obstacles::line_cells(a, b).collect(),
+        };
+        for cell in cells {
+            self.cells.entry(cell).or_default().push(self.obstacles.len());
+        }
+        self.obstacles.push(obstacle);
+    }
+
+    /// Whether a label could go at `rect`, it may cross jumplines if `cross_jumplines` is set.
+    fn free(&self, rect: Rect, cross_jumplines: bool) -> bool {
+        Obstacles::cells(rect)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .all(|&i| match self.obstacles[i] {
+                Obstacle::Dot(other) | Obstacle::Label(other) | Obstacle::Overlay(other) => !rect.overlaps(&other),
+                Obstacle::Jumpline(a, b) => cross_jumplines || !rect.crosses(a, b),
+            })
+    }
+}
+
+/// Finds a spot for the name of every visible star, in the order of the stars.
+///
+/// Each label takes the first spot around its star that is on the map and clear of
+/// other labels, star dots, overlays and jumplines. If there is none, it may cross jumplines,
+/// then it is shortened, and if even that doesn't fit it is left out.
+pub fn place_labels(cx: &cairo::Context, width: f64, height: f64, map: &Map) -> Vec<Label> {
+    let stars = map.visible_stars();
+    let mut obstacles = Obstacles::default();
+    for star in stars {
+        let (x, y) = (star.cords.0 * width, star.cords.1 * height);
+        let r = system_radius(star) + 1.0;
+        obstacles.add(Obstacle::Dot(Rect { x0: x - r, y0: y - r, x1: x + r, y1: y + r }));
+    }
+    if map.jumplines {
+        for &(a, b) in &map.jumps.edges {
+            let a = (stars[a].cords.0 * width, stars[a].cords.1 * height);
+            let b = (stars[b].cords.0 * width, stars[b].cords.1 * height);
+            obstacles.add(Obstacle::Jumpline(a, b));
+        }
+    }
+    for (_, (x, y), (w, h)) in overlays::arrange(cx, width, height, map) {
+        obstacles.add(Obstacle::Overlay(Rect { x0: x, y0: y, x1: x + w, y1: y + h }));
+    }
+
+    let layout = pangocairo::functions::create_layout(cx);
+    layout.set_font_description(Some(&map.font_desc));
+
+    let mut labels = Vec::new();
+    for star in stars {
+        let (x, y) = (star.cords.0 * width, star.cords.1 * height);
+        let offset = system_radius(star) + GAP;
+        let name: Vec<char> = star.system_name().chars().collect();
+        // the whole name clear of everything, then crossing jumplines, then shorter and shorter
+        let mut attempts = vec![(name.len(), false), (name.len(), true)];
+        let mut shorter: Vec<usize> = [name.len() * 2 / 3, name.len() / 2, SHORTEST].into_iter()
+            .filter(|&length| length >= SHORTEST && length < name.len())
+            .collect();
+        shorter.dedup();
+        attempts.extend(shorter.into_iter().map(|length| (length, true)));
+
+        let placed = attempts.into_iter().find_map(|(length, cross_jumplines)| {
+            let mut text: String = name[..length].iter().collect();
+            if length < name.len() {
+                text.push('…');
+            }
+            if map.display_class {
+                let class = match map.spectral_type {
+                    true => star.spectral_type(),
+                    false => star.class.to_string(),
+                };
+                text = format!("{text} [{class}]");
+            }
+            layout.set_text(&text);
+            let (w, h) = layout.pixel_size();
+            let (w, h) = (w as f64, h as f64);
+
+            let rect = CANDIDATES.iter()
+                .map(|&((dx, dy), (sx, sy))| {
+                    let x0 = x + dx * offset + sx * w;
+                    let y0 = y + dy * offset + sy * h;
+                    Rect { x0, y0, x1: x0 + w, y1: y0 + h }
+                })
+                .find(|rect| {
+                    let on_map = rect.x0 >= 0.0 && rect.y0 >= 0.0 && rect.x1 <= width && rect.y1 <= height;
+                    on_map && obstacles.free(*rect, cross_jumplines)
+                })?;
+            Some((rect, text, (layout.baseline() / PANGO_SCALE) as f64))
+        });
+
+        if let Some((rect, text, baseline)) = placed {
+            obstacles.add(Obstacle::Label(rect));
+            labels.push(Label { text, x: rect.x0, y: rect.y0 + baseline });
+        }
+    }
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    const RECT: Rect = Rect { x0: 10.0, y0: 10.0, x1: 20.0, y1: 20.0 };
+
+    #[test]
+    fn lines_through_the_rect_cross_it() {
+        assert!(RECT.crosses((0.0, 15.0), (30.0, 15.0)));
+        assert!(RECT.crosses((0.0, 0.0), (30.0, 30.0)));
+        assert!(RECT.crosses((15.0, 0.0), (15.0, 30.0)));
+        // ending inside, or inside altogether
+        assert!(RECT.crosses((0.0, 12.0), (15.0, 12.0)));
+        assert!(RECT.crosses((12.0, 12.0), (18.0, 18.0)));
+    }
+
+    #[test]
+    fn lines_past_the_rect_miss_it() {
+        assert!(!RECT.crosses((0.0, 5.0), (30.0, 5.0)));
+        assert!(!RECT.crosses((25.0, 0.0), (25.0, 30.0)));
+        // past a corner, and stopping short of the rect
+        assert!(!RECT.crosses((0.0, 15.0), (15.0, 0.0)));
+        assert!(!RECT.crosses((0.0, 15.0), (8.0, 15.0)));
+    }
+
+    #[test]
+    fn jumplines_only_take_the_cells_they_pass() {
+        let (a, b) = ((10.0, 20.0), (1000.0, 700.0));
+        let cells: HashSet<(i64, i64)> = Obstacles::line_cells(a, b).collect();
+        // far fewer than the 16 by 11 cells of the bounding box
+        assert!(cells.len() < 40);
+        for i in 0..=1000 {
+            let t = i as f64 / 1000.0;
+            let (x, y) = (a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1));
+            assert!(cells.contains(&((x / CELL).floor() as i64, (y / CELL).floor() as i64)));
+        }
+        // vertical lines stay in their column
+        let vertical: Vec<(i64, i64)> = Obstacles::line_cells((100.0, 300.0), (100.0, 10.0)).collect();
+        assert_eq!(vertical, [(1, 0), (1, 1), (1, 2), (1, 3), (1, 4)]);
+    }
+}
@@ -0,0 +1,58 @@
+use std::f64::consts::PI;
+
+use crate::generator::planets::PlanetKind;
+use crate::{generator::Star, map::Map};
+
+/// Draws the planets of a star around it. Orbits are spaced logarithmically,
+/// otherwise the inner planets would all end up on top of the star.
+pub fn draw_orrery(cx: &cairo::Context, width: f64, height: f64, star: &Star, map: &Map) {
+    cx.set_source_rgb(map.colors.wall.0, map.colors.wall.1, map.colors.wall.2);
+    cx.paint().unwrap();
+
+    let center = (width / 2.0, height / 2.0);
+    let star_radius = 6.0;
+    let outer = (width.min(height) / 2.0 - 8.0).max(star_radius + 1.0);
+    let inner = star_radius + 6.0;
+
+    let color = super::star_color(star.class, map);
+    cx.set_source_rgb(color.0, color.1, color.2);
+    cx.arc(center.0, center.1, star_radius, 0.0, 2.0 * PI);
+    cx.fill().unwrap();
+
+    let (Some(first), Some(last)) = (star.planets.first(), star.planets.last()) else {
+        return;
+    };
+    let (min, max) = (first.orbit.ln(), last.orbit.ln());
+    let radius = |orbit: f64| match max > min {
+        true => inner + (orbit.ln() - min) / (max - min) * (outer - inner),
+        false => (inner + outer) / 2.0,
+    };
+
+    cx.set_source_rgb(map.colors.jumplines.0, map.colors.jumplines.1, map.colors.jumplines.2);
+    cx.set_line_width(1.0);
+    for planet in &star.planets {
+        cx.new_path();
+        cx.arc(center.0, center.1, radius(planet.orbit), 0.0, 2.0 * PI);
+        cx.stroke().unwrap();
+    }
+
+    for (i, planet) in star.planets.iter().enumerate() {
+        // spread the planets around with the golden angle so they don't line up
+        let angle = i as f64 * PI * (3.0 - 5.0f64.sqrt());
+        let r = radius(planet.orbit);
+        let color = planet_color(planet.kind);
+        cx.set_source_rgb(color.0, color.1, color.2);
+        cx.arc(center.0 + r * angle.cos(), center.1 + r * angle.sin(), 1.5 + planet.size.sqrt(), 0.0, 2.0 * PI);
+        cx.fill().unwrap();
+    }
+}
+
+fn planet_color(kind: PlanetKind) -> (f64, f64, f64) {
+    match kind {
+        PlanetKind::Rocky => (0.7, 0.55, 0.45),
+        PlanetKind::Ocean => (0.3, 0.55, 0.9),
+        PlanetKind::GasGiant => (0.9, 0.75, 0.5),
+        PlanetKind::IceGiant => (0.55, 0.8, 0.9),
+        PlanetKind::IceDwarf => (0.85, 0.9, 0.95),
+    }
+}
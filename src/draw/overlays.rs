@@ -0,0 +1,352 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::generator::classes::StarClass;
+use crate::map::Map;
+
+use super::ROUTE_COLOR;
+
+/// Space between overlays and the edge of the map, and inside their frames.
+const PADDING: f64 = 8.0;
+/// Glyphs in the legend are all this big, so they are easy to tell apart.
+const LEGEND_RADIUS: f64 = 4.0;
+const COMPASS_RADIUS: f64 = 24.0;
+
+/// Where on the map an overlay goes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    pub const ALL: [Corner; 4] = [Corner::TopLeft, Corner::TopRight, Corner::BottomLeft, Corner::BottomRight];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Corner::TopLeft => "top-left",
+            Corner::TopRight => "top-right",
+            Corner::BottomLeft => "bottom-left",
+            Corner::BottomRight => "bottom-right",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Corner> {
+        Corner::ALL.into_iter().find(|corner| corner.name().eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Overlay {
+    pub visible: bool,
+    pub corner: Corner,
+}
+
+/// The overlays that can be drawn on top of a map, in the order they stack in a corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverlayKind {
+    Title,
+    Legend,
+    ScaleBar,
+    Compass,
+}
+
+impl OverlayKind {
+    pub const ALL: [OverlayKind; 4] = [OverlayKind::Title, OverlayKind::Legend, OverlayKind::ScaleBar, OverlayKind::Compass];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OverlayKind::Title => "title",
+            OverlayKind::Legend => "legend",
+            OverlayKind::ScaleBar => "scale-bar",
+            OverlayKind::Compass => "compass",
+        }
+    }
+
+    /// Name shown in the window.
+    pub fn label(self) -> &'static str {
+        match self {
+            OverlayKind::Title => "Title",
+            OverlayKind::Legend => "Legend",
+            OverlayKind::ScaleBar => "Scale bar",
+            OverlayKind::Compass => "Compass",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<OverlayKind> {
+        OverlayKind::ALL.into_iter().find(|kind| kind.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Which overlays are shown and where, all hidden by default.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Overlays {
+    pub title: Overlay,
+    pub legend: Overlay,
+    pub scale_bar: Overlay,
+    pub compass: Overlay,
+}
+
+impl Default for Overlays {
+    fn default() -> Self {
+        let hidden = |corner| Overlay { visible: false, corner };
+        Overlays {
+            title: hidden(Corner::TopLeft),
+            legend: hidden(Corner::BottomLeft),
+            scale_bar: hidden(Corner::BottomRight),
+            compass: hidden(Corner::TopRight),
+        }
+    }
+}
+
+impl Overlays {
+    pub fn get(&self, kind: OverlayKind) -> &Overlay {
+        match kind {
+            OverlayKind::Title => &self.title,
+            OverlayKind::Legend => &self.legend,
+            OverlayKind::ScaleBar => &self.scale_bar,
+            OverlayKind::Compass => &self.compass,
+        }
+    }
+
+    pub fn get_mut(&mut self, kind: OverlayKind) -> &mut Overlay {
+        match kind {
+            OverlayKind::Title => &mut self.title,
+            OverlayKind::Legend => &mut self.legend,
+            OverlayKind::ScaleBar => &mut self.scale_bar,
+            OverlayKind::Compass => &mut self.compass,
+        }
+    }
+}
+
+/// Draws the visible overlays.
+pub fn draw_overlays(cx: &cairo::Context, width: f64, height: f64, map: &Map) {
+    for (kind, (x, y), (w, h)) in arrange(cx, width, height, map) {
+        draw_frame(cx, x, y, w, h, kind == OverlayKind::Title, map);
+        match kind {
+            OverlayKind::Title => draw_title(cx, x, y, map),
+            OverlayKind::Legend => draw_legend(cx, x, y, map),
+            OverlayKind::ScaleBar => draw_scale_bar(cx, x, y, width, map),
+            OverlayKind::Compass => draw_compass(cx, x, y, map),
+        }
+    }
+}
+
+/// Position and size of the visible overlays with their frames,
+/// the ones sharing a corner are stacked away from it.
+pub fn arrange(cx: &cairo::Context, width: f64, height: f64, map: &Map) -> Vec<(OverlayKind, (f64, f64), (f64, f64))> {
+    let mut placed = Vec::new();
+    for corner in Corner::ALL {
+        let mut offset = PADDING;
+        for kind in OverlayKind::ALL {
+            let overlay = map.overlays.get(kind);
+            if !overlay.visible || overlay.corner != corner {
+                continue;
+            }
+            let Some((w, h)) = size(cx, width, kind, map) else {
+                continue;
+            };
+            let x = match corner {
+                Corner::TopLeft | Corner::BottomLeft => PADDING,
+                Corner::TopRight | Corner::BottomRight => width - w - PADDING,
+            };
+            let y = match corner {
+                Corner::TopLeft | Corner::TopRight => offset,
+                Corner::BottomLeft | Corner::BottomRight => height - h - offset,
+            };
+            placed.push((kind, (x, y), (w, h)));
+            offset += h + PADDING;
+        }
+    }
+    placed
+}
+
+/// Size of an overlay with its frame, `None` if there is nothing to show.
+fn size(cx: &cairo::Context, width: f64, kind: OverlayKind, map: &Map) -> Option<(f64, f64)> {
+    let (w, h) = match kind {
+        OverlayKind::Title => {
+            let (title, seed) = title_layouts(cx, map);
+            let (title, seed) = (title.pixel_size(), seed.pixel_size());
+            (title.0.max(seed.0) as f64, (title.1 + seed.1) as f64)
+        },
+        OverlayKind::Legend => {
+            let rows = legend_rows(cx, map);
+            if rows.is_empty() {
+                return None;
+            }
+            let text = rows.iter().map(|(_, row)| row.pixel_size().0 as f64).fold(0.0, f64::max);
+            (LEGEND_RADIUS * 6.0 + text, rows.iter().map(|(_, row)| legend_line(row)).sum())
+        },
+        OverlayKind::ScaleBar => {
+            let (length, pixels) = scale_length(width, map);
+            let label = scale_label(cx, map, length);
+            (pixels.max(label.pixel_size().0 as f64), label.pixel_size().1 as f64 + 8.0)
+        },
+        OverlayKind::Compass => (COMPASS_RADIUS * 2.0, COMPASS_RADIUS * 2.0 + text_layout(cx, map, "N").pixel_size().1 as f64),
+    };
+    Some((w + 2.0 * PADDING, h + 2.0 * PADDING))
+}
+
+/// The background behind an overlay, so it stays readable over stars and jumplines.
+/// The title gets a double border, like the cartouche of an old map.
+fn draw_frame(cx: &cairo::Context, x: f64, y: f64, w: f64, h: f64, double: bool, map: &Map) {
+    let (ink, wall) = (map.colors.starnames, map.colors.wall);
+    cx.new_path();
+    cx.rectangle(x, y, w, h);
+    cx.set_source_rgb(wall.0, wall.1, wall.2);
+    cx.fill_preserve().unwrap();
+    cx.set_source_rgb(ink.0, ink.1, ink.2);
+    cx.set_line_width(1.0);
+    cx.stroke().unwrap();
+    if double {
+        cx.rectangle(x + 3.0, y + 3.0, w - 6.0, h - 6.0);
+        cx.stroke().unwrap();
+    }
+}
+
+fn text_layout(cx: &cairo::Context, map: &Map, text: &str) -> gtk::pango::Layout {
+    let layout = pangocairo::functions::create_layout(cx);
+    layout.set_font_description(Some(&map.font_desc));
+    layout.set_text(text);
+    layout
+}
+
+fn show_text(cx: &cairo::Context, x: f64, y: f64, layout: &gtk::pango::Layout, map: &Map) {
+    let ink = map.colors.starnames;
+    cx.set_source_rgb(ink.0, ink.1, ink.2);
+    cx.move_to(x, y);
+    pangocairo::functions::show_layout(cx, layout);
+}
+
+/// The map title in a bigger font and the seed below it.
+fn title_layouts(cx: &cairo::Context, map: &Map) -> (gtk::pango::Layout, gtk::pango::Layout) {
+    let title = text_layout(cx, map, if map.title.is_empty() { "Star map" } else { &map.title });
+    let mut font = map.font_desc.clone();
+    font.set_size(font.size() * 3 / 2);
+    title.set_font_description(Some(&font));
+    (title, text_layout(cx, map, &format!("Seed {:#x}", map.seed)))
+}
+
+fn draw_title(cx: &cairo::Context, x: f64, y: f64, map: &Map) {
+    let (title, seed) = title_layouts(cx, map);
+    show_text(cx, x + PADDING, y + PADDING, &title, map);
+    show_text(cx, x + PADDING, y + PADDING + title.pixel_size().1 as f64, &seed, map);
+}
+
+/// What a row of the legend explains.
+enum Key {
+    Class(StarClass),
+    /// A line in this color
+    Line((f64,f64,f64)),
+}
+
+/// A row for every class on the map, then the jumplines and the route if they are drawn.
+fn legend_rows(cx: &cairo::Context, map: &Map) -> Vec<(Key, gtk::pango::Layout)> {
+    let stars = map.visible_stars();
+    let mut rows: Vec<_> = StarClass::ALL.into_iter()
+        .filter(|&class| stars.iter().any(|star| star.class == class || star.companions.iter().any(|c| c.class == class)))
+        .map(|class| (Key::Class(class), text_layout(cx, map, &format!("{} {}", class.code(), class.name()))))
+        .collect();
+    if map.jumplines {
+        rows.push((Key::Line(map.colors.jumplines), text_layout(cx, map, &format!("jump of up to {} ly", map.jumpdistance))));
+    }
+    if !map.route.is_empty() {
+        rows.push((Key::Line(ROUTE_COLOR), text_layout(cx, map, "route")));
+    }
+    rows
+}
+
+fn legend_line(row: &gtk::pango::Layout) -> f64 {
+    (row.pixel_size().1 as f64).max(LEGEND_RADIUS * 3.0)
+}
+
+fn draw_legend(cx: &cairo::Context, x: f64, y: f64, map: &Map) {
+    let rows = legend_rows(cx, map);
+    let center = x + PADDING + LEGEND_RADIUS * 2.0;
+    let mut top = y + PADDING;
+    for (key, row) in &rows {
+        let middle = top + legend_line(row) / 2.0;
+        match *key {
+            Key::Class(class) => super::draw_body(cx, center, middle, LEGEND_RADIUS, class, map),
+            Key::Line(color) => {
+                cx.set_source_rgb(color.0, color.1, color.2);
+                cx.set_line_width(3.0);
+                cx.move_to(center - LEGEND_RADIUS * 2.0, middle);
+                cx.line_to(center + LEGEND_RADIUS * 2.0, middle);
+                cx.stroke().unwrap();
+            },
+        }
+        show_text(cx, center + LEGEND_RADIUS * 4.0, middle - row.pixel_size().1 as f64 / 2.0, row, map);
+        top += legend_line(row);
+    }
+}
+
+/// The longest round length in light-years, 1, 2 or 5 times a power of ten,
+/// that takes up at most a quarter of the map width. Also returns it in pixels.
+/// Maps are stretched to the window, so the length only holds horizontally.
+fn scale_length(width: f64, map: &Map) -> (f64, f64) {
+    let pixels_per_ly = width / map.scale;
+    let max = width / 4.0 / pixels_per_ly;
+    let magnitude = 10.0f64.powf(max.log10().floor());
+    let length = [5.0, 2.0, 1.0].into_iter()
+        .map(|step| step * magnitude)
+        .find(|&length| length <= max)
+        .unwrap_or(magnitude);
+    (length, length * pixels_per_ly)
+}
+
+fn scale_label(cx: &cairo::Context, map: &Map, length: f64) -> gtk::pango::Layout {
+    text_layout(cx, map, &format!("{length} ly (horizontal)"))
+}
+
+fn draw_scale_bar(cx: &cairo::Context, x: f64, y: f64, width: f64, map: &Map) {
+    let (length, pixels) = scale_length(width, map);
+    let label = scale_label(cx, map, length);
+    show_text(cx, x + PADDING, y + PADDING, &label, map);
+
+    // a line with ticks at both ends and the middle
+    let (left, base) = (x + PADDING, y + PADDING + label.pixel_size().1 as f64 + 6.0);
+    cx.set_line_width(2.0);
+    cx.move_to(left, base);
+    cx.line_to(left + pixels, base);
+    for tick in [0.0, 0.5, 1.0] {
+        cx.move_to(left + tick * pixels, base);
+        cx.line_to(left + tick * pixels, base - if tick == 0.5 { 3.0 } else { 6.0 });
+    }
+    cx.stroke().unwrap();
+}
+
+/// Two crossed needles, the north one filled, with an N above.
+fn draw_compass(cx: &cairo::Context, x: f64, y: f64, map: &Map) {
+    let ink = map.colors.starnames;
+    let label = text_layout(cx, map, "N");
+    let (label_width, label_height) = (label.pixel_size().0 as f64, label.pixel_size().1 as f64);
+    let (cx0, cy0) = (x + PADDING + COMPASS_RADIUS, y + PADDING + label_height + COMPASS_RADIUS);
+    show_text(cx, cx0 - label_width / 2.0, y + PADDING, &label, map);
+
+    cx.set_source_rgb(ink.0, ink.1, ink.2);
+    cx.set_line_width(1.0);
+    for i in 0..4 {
+        let angle = -PI / 2.0 + i as f64 * PI / 2.0;
+        let (tip, side) = (COMPASS_RADIUS * 0.95, COMPASS_RADIUS * 0.18);
+        let point = |r: f64, a: f64| (cx0 + r * a.cos(), cy0 + r * a.sin());
+        let (tx, ty) = point(tip, angle);
+        let (lx, ly) = point(side, angle - PI / 2.0);
+        let (rx, ry) = point(side, angle + PI / 2.0);
+        cx.move_to(lx, ly);
+        cx.line_to(tx, ty);
+        cx.line_to(rx, ry);
+        cx.close_path();
+        if i == 0 {
+            cx.fill_preserve().unwrap();
+        }
+        cx.stroke().unwrap();
+    }
+    cx.arc(cx0, cy0, COMPASS_RADIUS * 0.6, 0.0, 2.0 * PI);
+    cx.stroke().unwrap();
+}
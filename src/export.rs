@@ -0,0 +1,214 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use gtk::pango;
+use gtk::pango::ffi::PANGO_SCALE;
+
+use crate::draw::{self, Label};
+use crate::map::Map;
+
+pub mod catalogue;
+mod pdf;
+
+pub use pdf::{Paper, PageSetup};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Png,
+    Svg,
+    Pdf,
+}
+
+impl Format {
+    pub const ALL: [Format; 3] = [Format::Png, Format::Svg, Format::Pdf];
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Format::Png => "png",
+            Format::Svg => "svg",
+            Format::Pdf => "pdf",
+        }
+    }
+
+    /// Name of the file filter for the format.
+    fn filter_name(self) -> &'static str {
+        match self {
+            Format::Png => "PNG image",
+            Format::Svg => "SVG image",
+            Format::Pdf => "PDF document",
+        }
+    }
+
+    /// The format a filter from [`file_filters`] is for.
+    pub fn from_filter(filter: &gtk::FileFilter) -> Option<Format> {
+        let name = filter.name()?;
+        Format::ALL.into_iter().find(|format| format.filter_name() == name)
+    }
+
+    /// `path` with the suffix of this format, replacing the suffix of another format.
+    pub fn with_suffix(self, path: &Path) -> PathBuf {
+        match path.extension() {
+            Some(_) if Format::from_path(path) == Ok(self) => path.to_owned(),
+            Some(_) if Format::from_path(path).is_ok() => path.with_extension(self.suffix()),
+            // no suffix, or a dot that belongs to the name
+            _ => {
+                let mut path = path.as_os_str().to_owned();
+                path.push(".");
+                path.push(self.suffix());
+                path.into()
+            },
+        }
+    }
+
+    /// Picks the format from the file extension, defaulting to PNG when there is none.
+    pub fn from_path(path: &Path) -> Result<Format, String> {
+        match path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).as_deref() {
+            None | Some("png") => Ok(Format::Png),
+            Some("svg") => Ok(Format::Svg),
+            Some("pdf") => Ok(Format::Pdf),
+            Some(ext) => Err(format!("Unsupported file type: .{ext}")),
+        }
+    }
+}
+
+/// Size of exported images.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ImageSize {
+    pub width: u32,
+    pub height: u32,
+    /// Resolution the map is drawn at, at 96 it looks the same as on screen
+    pub dpi: f64,
+}
+
+impl Default for ImageSize {
+    fn default() -> Self {
+        ImageSize { width: 1920, height: 1080, dpi: 96.0 }
+    }
+}
+
+impl ImageSize {
+    /// The size the whole map has in the page setup, at `dpi`.
+    pub fn for_paper(setup: &PageSetup, dpi: f64) -> ImageSize {
+        let (width, height) = setup.map_size();
+        ImageSize {
+            width: (width / 72.0 * dpi).round() as u32,
+            height: (height / 72.0 * dpi).round() as u32,
+            dpi,
+        }
+    }
+
+    fn scale(&self) -> f64 {
+        self.dpi / 96.0
+    }
+
+    /// Size of the map before it is scaled up to the resolution
+    fn logical_size(&self) -> (f64, f64) {
+        (self.width as f64 / self.scale(), self.height as f64 / self.scale())
+    }
+}
+
+/// Adds a filter for every export format to a file dialog.
+pub fn file_filters() -> gtk::gio::ListStore {
+    let filters = gtk::gio::ListStore::new::<gtk::FileFilter>();
+    for format in Format::ALL {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(format.filter_name()));
+        filter.add_suffix(format.suffix());
+        filters.append(&filter);
+    }
+    filters
+}
+
+/// Writes the map to `path`. Images have the given size, documents follow the page setup.
+pub fn export(path: &Path, size: &ImageSize, map: &Map, setup: &PageSetup) -> Result<(), String> {
+    if size.width == 0 || size.height == 0 || size.dpi <= 0.0 {
+        return Err(format!("Invalid image size: {}x{} at {} dpi", size.width, size.height, size.dpi));
+    }
+    match Format::from_path(path)? {
+        Format::Png => write_png(path, size, map),
+        Format::Svg => write_svg(path, size, map),
+        Format::Pdf => pdf::write_pdf(path, map, setup),
+    }
+}
+
+fn write_png(path: &Path, size: &ImageSize, map: &Map) -> Result<(), String> {
+    let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, size.width as i32, size.height as i32)
+        .map_err(|e| format!("while creating surface: {e}"))?;
+    let cx = cairo::Context::new(&surface).map_err(|e| format!("while creating context: {e}"))?;
+    let (width, height) = size.logical_size();
+    cx.scale(size.scale(), size.scale());
+    draw::draw(&cx, width, height, map);
+
+    let mut file = File::create(path).map_err(|e| format!("while creating {}: {e}", path.display()))?;
+    surface.write_to_png(&mut file).map_err(|e| format!("while writing {}: {e}", path.display()))
+}
+
+/// Cairo turns text into glyph outlines, so the star names are left out
+/// of the drawing and added afterwards as `<text>` elements.
+fn write_svg(path: &Path, size: &ImageSize, map: &Map) -> Result<(), String> {
+    let mut surface = cairo::SvgSurface::for_stream(size.width as f64, size.height as f64, Vec::<u8>::new())
+        .map_err(|e| format!("while creating surface: {e}"))?;
+    surface.set_document_unit(cairo::SvgUnit::Px);
+    let cx = cairo::Context::new(&surface).map_err(|e| format!("while creating context: {e}"))?;
+    let (width, height) = size.logical_size();
+    cx.scale(size.scale(), size.scale());
+    let labels = draw::draw_without_labels(&cx, width, height, map);
+    drop(cx);
+
+    let stream = surface.finish_output_stream().map_err(|e| format!("while rendering: {e}"))?;
+    let mut svg = String::from_utf8(*stream.downcast::<Vec<u8>>().unwrap())
+        .map_err(|e| format!("while rendering: {e}"))?;
+
+    let end = svg.rfind("</svg>").ok_or("cairo produced an invalid svg")?;
+    svg.insert_str(end, &svg_labels(&labels, size.scale(), map));
+
+    std::fs::write(path, svg).map_err(|e| format!("while writing {}: {e}", path.display()))
+}
+
+fn svg_labels(labels: &[Label], scale: f64, map: &Map) -> String {
+    let font = &map.font_desc;
+    // pangocairo lays text out at 96 dpi
+    let size = match font.is_size_absolute() {
+        true => (font.size() / PANGO_SCALE) as f64,
+        false => (font.size() / PANGO_SCALE) as f64 * 96.0 / 72.0,
+    };
+    let style = match font.style() {
+        pango::Style::Italic => "italic",
+        pango::Style::Oblique => "oblique",
+        _ => "normal",
+    };
+    let (r, g, b) = map.colors.starnames;
+
+    let mut out = format!(
+        "<g transform=\"scale({scale})\" font-family=\"{}\" font-size=\"{size}\" font-weight=\"{}\" font-style=\"{style}\" fill=\"rgb({},{},{})\">\n",
+        escape(&font.family().unwrap_or("sans-serif".into())),
+        font_weight(font.weight()),
+        (r * 255.0).round(), (g * 255.0).round(), (b * 255.0).round(),
+    );
+    for label in labels {
+        out.push_str(&format!("<text x=\"{:.2}\" y=\"{:.2}\">{}</text>\n", label.x, label.y, escape(&label.text)));
+    }
+    out.push_str("</g>\n");
+    out
+}
+
+fn font_weight(weight: pango::Weight) -> u32 {
+    match weight {
+        pango::Weight::Thin => 100,
+        pango::Weight::Ultralight => 200,
+        pango::Weight::Light | pango::Weight::Semilight | pango::Weight::Book => 300,
+        pango::Weight::Medium => 500,
+        pango::Weight::Semibold => 600,
+        pango::Weight::Bold => 700,
+        pango::Weight::Ultrabold => 800,
+        pango::Weight::Heavy | pango::Weight::Ultraheavy => 900,
+        _ => 400,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,114 @@
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::generator::{classes::StarClass, companions::Companion, planets::Planet, properties::Properties};
+use crate::map::Map;
+
+/// One row of the catalogue, a visible star and the stars it has jumplines to.
+/// The CSV only has the number of planets and the spectral types of the companions.
+#[derive(Serialize)]
+struct Entry<'a> {
+    name: &'a str,
+    class: StarClass,
+    spectral_type: String,
+    #[serde(flatten)]
+    properties: &'a Properties,
+    planets: &'a [Planet],
+    companions: &'a [Companion],
+    /// Position on the map, from 0 to 1
+    x: f64,
+    y: f64,
+    /// Position in light-years from the top left corner
+    x_ly: f64,
+    y_ly: f64,
+    neighbours: Vec<&'a str>,
+}
+
+pub fn file_filters() -> gtk::gio::ListStore {
+    let filters = gtk::gio::ListStore::new::<gtk::FileFilter>();
+    for (name, suffix) in [("CSV spreadsheet", "csv"), ("JSON", "json")] {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(name));
+        filter.add_suffix(suffix);
+        filters.append(&filter);
+    }
+    filters
+}
+
+/// Writes the visible stars as CSV or JSON, depending on the extension of `path`.
+pub fn write_catalogue(path: &Path, map: &Map) -> Result<(), String> {
+    let entries: Vec<Entry> = map.visible_stars().iter().enumerate().map(|(i, star)| Entry {
+        name: &star.name,
+        class: star.class,
+        spectral_type: star.spectral_type(),
+        properties: &star.properties,
+        planets: &star.planets,
+        companions: &star.companions,
+        x: star.cords.0,
+        y: star.cords.1,
+        x_ly: star.cords.0 * map.scale,
+        y_ly: star.cords.1 * map.scale,
+        neighbours: map.jump_neighbours(i).map(|n| map.stars[n].name.as_str()).collect(),
+    }).collect();
+
+    let contents = match path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).as_deref() {
+        Some("csv") => csv(&entries),
+        Some("json") => serde_json::to_string_pretty(&entries).map_err(|e| format!("while serializing: {e}"))?,
+        _ => return Err("The catalogue can only be saved as .csv or .json".into()),
+    };
+
+    std::fs::write(path, contents).map_err(|e| format!("while writing {}: {e}", path.display()))
+}
+
+fn csv(entries: &[Entry]) -> String {
+    let mut out = String::from("name,class,spectral_type,mass,radius,temperature,luminosity,age,planets,companions,x,y,x_ly,y_ly,neighbours\n");
+    for entry in entries {
+        out.push_str(&format!(
+            "{},{},{},{:.3},{:.3},{:.0},{:.4},{:.3},{},{},{},{},{:.3},{:.3},{}\n",
+            csv_field(entry.name),
+            entry.class,
+            entry.spectral_type,
+            entry.properties.mass,
+            entry.properties.radius,
+            entry.properties.temperature,
+            entry.properties.luminosity,
+            entry.properties.age,
+            entry.planets.len(),
+            entry.companions.iter().map(Companion::spectral_type).collect::<Vec<_>>().join(";"),
+            entry.x,
+            entry.y,
+            entry.x_ly,
+            entry.y_ly,
+            csv_field(&entry.neighbours.join(";")),
+        ));
+    }
+    out
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_fields_stay_as_they_are() {
+        assert_eq!(csv_field("Sol"), "Sol");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn special_fields_are_quoted() {
+        assert_eq!(csv_field("Alcor A/B, Mizar"), "\"Alcor A/B, Mizar\"");
+        assert_eq!(csv_field("\"Bob\""), "\"\"\"Bob\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("two\r\nlines"), "\"two\r\nlines\"");
+    }
+}
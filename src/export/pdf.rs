@@ -0,0 +1,242 @@
+use std::f64::consts::PI;
+use std::path::Path;
+
+use gtk::pango;
+
+use crate::draw;
+use crate::map::Map;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Paper {
+    A4,
+    A3,
+    Letter,
+    Tabloid,
+}
+
+impl Paper {
+    pub const ALL: [Paper; 4] = [Paper::A4, Paper::A3, Paper::Letter, Paper::Tabloid];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Paper::A4 => "A4",
+            Paper::A3 => "A3",
+            Paper::Letter => "Letter",
+            Paper::Tabloid => "Tabloid",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Paper> {
+        Paper::ALL.into_iter().find(|paper| paper.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Portrait size in points
+    pub fn size(self) -> (f64, f64) {
+        match self {
+            Paper::A4 => (mm(210.0), mm(297.0)),
+            Paper::A3 => (mm(297.0), mm(420.0)),
+            Paper::Letter => (612.0, 792.0),
+            Paper::Tabloid => (792.0, 1224.0),
+        }
+    }
+}
+
+/// How a map is laid out on paper. Lengths are in millimeters.
+#[derive(Clone, PartialEq, Debug)]
+pub struct PageSetup {
+    pub paper: Paper,
+    pub landscape: bool,
+    pub margin: f64,
+    pub bleed: f64,
+    /// Number of pages the map is tiled over horizontally
+    pub columns: u32,
+    pub rows: u32,
+    /// How much neighbouring tiles share, to glue them together
+    pub overlap: f64,
+}
+
+impl Default for PageSetup {
+    fn default() -> Self {
+        PageSetup {
+            paper: Paper::A4,
+            landscape: true,
+            margin: 10.0,
+            bleed: 0.0,
+            columns: 1,
+            rows: 1,
+            overlap: 10.0,
+        }
+    }
+}
+
+impl PageSetup {
+    /// Trimmed page size in points
+    pub fn page_size(&self) -> (f64, f64) {
+        let (width, height) = self.paper.size();
+        match self.landscape {
+            true => (height, width),
+            false => (width, height),
+        }
+    }
+
+    /// Size of the whole map over all tiles, in points
+    pub fn map_size(&self) -> (f64, f64) {
+        let (width, height) = self.printable_size();
+        let overlap = mm(self.overlap);
+        (
+            self.columns as f64 * (width - overlap) + overlap,
+            self.rows as f64 * (height - overlap) + overlap,
+        )
+    }
+
+    fn printable_size(&self) -> (f64, f64) {
+        let (width, height) = self.page_size();
+        (width - 2.0 * mm(self.margin), height - 2.0 * mm(self.margin))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let (width, height) = self.printable_size();
+        if width <= 0.0 || height <= 0.0 {
+            return Err("The margins are larger than the paper".into());
+        }
+        if self.columns == 0 || self.rows == 0 {
+            return Err("The map has to span at least one page".into());
+        }
+        if (self.columns > 1 && mm(self.overlap) >= width) || (self.rows > 1 && mm(self.overlap) >= height) {
+            return Err("The overlap is larger than a page".into());
+        }
+        Ok(())
+    }
+}
+
+pub fn mm(mm: f64) -> f64 {
+    mm * 72.0 / 25.4
+}
+
+pub fn write_pdf(path: &Path, map: &Map, setup: &PageSetup) -> Result<(), String> {
+    setup.validate()?;
+
+    let (page_width, page_height) = setup.page_size();
+    let (width, height) = setup.printable_size();
+    let (map_width, map_height) = setup.map_size();
+    let margin = mm(setup.margin);
+    let bleed = mm(setup.bleed);
+    let overlap = mm(setup.overlap);
+
+    let surface = cairo::PdfSurface::new(page_width + 2.0 * bleed, page_height + 2.0 * bleed, path)
+        .map_err(|e| format!("while creating {}: {e}", path.display()))?;
+    let title = match map.title.is_empty() {
+        true => format!("Starmap {:#x}", map.seed),
+        false => map.title.clone(),
+    };
+    surface.set_metadata(cairo::PdfMetadata::Title, &title)
+        .map_err(|e| format!("while writing metadata: {e}"))?;
+    let cx = cairo::Context::new(&surface).map_err(|e| format!("while creating context: {e}"))?;
+
+    for row in 0..setup.rows {
+        for column in 0..setup.columns {
+            // the background runs into the bleed, the stars stop at the margin
+            cx.set_source_rgb(map.colors.wall.0, map.colors.wall.1, map.colors.wall.2);
+            cx.paint().unwrap();
+
+            cx.save().unwrap();
+            cx.translate(bleed + margin, bleed + margin);
+            cx.rectangle(0.0, 0.0, width, height);
+            cx.clip();
+            cx.translate(-(column as f64) * (width - overlap), -(row as f64) * (height - overlap));
+            draw::draw(&cx, map_width, map_height, map);
+            if setup.columns > 1 || setup.rows > 1 {
+                draw_alignment_marks(&cx, setup, map);
+            }
+            cx.restore().unwrap();
+
+            if bleed > 0.0 {
+                draw_crop_marks(&cx, page_width, page_height, bleed, map);
+            }
+            if setup.columns > 1 || setup.rows > 1 {
+                draw_page_label(&cx, row, column, setup, map);
+            }
+
+            cx.show_page().map_err(|e| format!("while writing page: {e}"))?;
+        }
+    }
+
+    drop(cx);
+    surface.finish();
+    Ok(())
+}
+
+/// Registration marks in the middle of every overlap, so they end up
+/// on both neighbouring pages at the same spot of the map.
+fn draw_alignment_marks(cx: &cairo::Context, setup: &PageSetup, map: &Map) {
+    let (width, height) = setup.printable_size();
+    let overlap = mm(setup.overlap);
+
+    let mut marks = Vec::new();
+    for seam in 1..setup.columns {
+        let x = seam as f64 * (width - overlap) + overlap / 2.0;
+        for row in 0..setup.rows {
+            marks.push((x, row as f64 * (height - overlap) + height / 2.0));
+        }
+    }
+    for seam in 1..setup.rows {
+        let y = seam as f64 * (height - overlap) + overlap / 2.0;
+        for column in 0..setup.columns {
+            marks.push((column as f64 * (width - overlap) + width / 2.0, y));
+        }
+    }
+
+    let radius = (overlap / 2.0).min(mm(4.0));
+    cx.set_source_rgb(map.colors.starnames.0, map.colors.starnames.1, map.colors.starnames.2);
+    cx.set_line_width(0.5);
+    for (x, y) in marks {
+        cx.new_path();
+        cx.arc(x, y, radius / 2.0, 0.0, 2.0 * PI);
+        cx.move_to(x - radius, y);
+        cx.line_to(x + radius, y);
+        cx.move_to(x, y - radius);
+        cx.line_to(x, y + radius);
+        cx.stroke().unwrap();
+    }
+}
+
+/// Marks in the bleed that show where to cut the page.
+fn draw_crop_marks(cx: &cairo::Context, page_width: f64, page_height: f64, bleed: f64, map: &Map) {
+    cx.set_source_rgb(map.colors.starnames.0, map.colors.starnames.1, map.colors.starnames.2);
+    cx.set_line_width(0.5);
+    for x in [bleed, bleed + page_width] {
+        for y in [bleed, bleed + page_height] {
+            let outward_x = if x == bleed { -bleed } else { bleed };
+            let outward_y = if y == bleed { -bleed } else { bleed };
+            cx.move_to(x, y + outward_y);
+            cx.line_to(x, y + outward_y / 3.0);
+            cx.move_to(x + outward_x, y);
+            cx.line_to(x + outward_x / 3.0, y);
+        }
+    }
+    cx.stroke().unwrap();
+}
+
+/// Names the tile in the bottom margin, if there is room for it.
+fn draw_page_label(cx: &cairo::Context, row: u32, column: u32, setup: &PageSetup, map: &Map) {
+    let margin = mm(setup.margin);
+    if margin < mm(6.0) {
+        return;
+    }
+
+    let mut font = map.font_desc.clone();
+    font.set_size(8 * pango::SCALE);
+    let layout = pangocairo::functions::create_layout(cx);
+    layout.set_font_description(Some(&font));
+    layout.set_text(&format!(
+        "{:#x}  row {} of {}, column {} of {}",
+        map.seed, row + 1, setup.rows, column + 1, setup.columns,
+    ));
+
+    let (_, page_height) = setup.page_size();
+    let bleed = mm(setup.bleed);
+    let text_height = (layout.size().1 / pango::SCALE) as f64;
+    cx.set_source_rgb(map.colors.starnames.0, map.colors.starnames.1, map.colors.starnames.2);
+    cx.move_to(bleed + margin, bleed + page_height - (margin + text_height) / 2.0);
+    pangocairo::functions::show_layout(cx, &layout);
+}
@@ -1,45 +1,222 @@
 use std::sync::LazyLock;
 use rand::prelude::*;
 use rand_distr;
+use serde::{Deserialize, Serialize};
 
+pub mod classes;
+pub mod companions;
+mod markov;
 pub mod names;
+pub mod placement;
+pub mod planets;
+pub mod properties;
+
+use classes::StarClass;
+use companions::Companion;
+use names::NameSource;
+use placement::{Placement, Placer};
+use planets::Planet;
+use properties::Properties;
 
 /// <https://en.wikipedia.org/wiki/Stellar_classification#Harvard_spectral_classification>
-pub static STARCLASSES: &'static [(char, f64)] = &[('O', 0.00003), ('B', 0.12), ('A', 0.61), ('F', 3.0), ('G', 7.6), ('K', 12.0), ('M', 76.0)];
+pub static STARCLASSES: &[(StarClass, f64)] = &[(StarClass::O, 0.00003), (StarClass::B, 0.12), (StarClass::A, 0.61), (StarClass::F, 3.0), (StarClass::G, 7.6), (StarClass::K, 12.0), (StarClass::M, 76.0)];
+
+/// Percentage of all stars that are one of the other classes instead.
+/// They are drawn apart from [`STARCLASSES`], so seeds keep their ordinary stars.
+pub static EXOTIC_CLASSES: &[(StarClass, f64)] = &[
+    (StarClass::WhiteDwarf, 6.0),
+    (StarClass::L, 3.0),
+    (StarClass::T, 2.0),
+    (StarClass::Y, 0.5),
+    (StarClass::NeutronStar, 0.1),
+    (StarClass::BlackHole, 0.02),
+    (StarClass::WolfRayet, 0.002),
+];
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Star {
-    pub name: &'static str,
-    pub class: char,
-    pub _planets: u8,
+    pub name: String,
+    pub class: StarClass,
+    #[serde(default)]
+    pub planets: Vec<Planet>,
     pub cords: (f64, f64),
+    #[serde(default)]
+    pub properties: Properties,
+    /// The other stars of a multiple system
+    #[serde(default)]
+    pub companions: Vec<Companion>,
+}
+
+impl Star {
+    /// Distance to another star in map coordinates, multiply by the map scale for light-years.
+    pub fn distance(&self, other: &Star) -> f64 {
+        ((self.cords.0 - other.cords.0).powi(2) + (self.cords.1 - other.cords.1).powi(2)).sqrt()
+    }
+
+    /// Full spectral type like G2V.
+    pub fn spectral_type(&self) -> String {
+        self.class.spectral_type(&self.properties)
+    }
+
+    /// Name of the whole system, like "Alcor A/B" for a binary.
+    pub fn system_name(&self) -> String {
+        if self.companions.is_empty() {
+            return self.name.clone();
+        }
+        let members: Vec<String> = (0..=self.companions.len()).map(|i| ((b'A' + i as u8) as char).to_string()).collect();
+        format!("{} {}", self.name, members.join("/"))
+    }
 }
 
-pub static AMOUNT: usize = 512;
+/// Upper limit for the star count in the UI, not for the generator
+pub static MAX_STARS: u32 = 100_000;
 static PLANET_DISTRIBUTION: LazyLock<rand_distr::Normal<f32>> = LazyLock::new(|| rand_distr::Normal::new(7.0, 4.0).unwrap());
 
-// <SmallRng as SeedableRng>::Seed could be used here to seed from strings
-pub fn generate_stars_with_seed(seed: u64) -> Vec<Star> {
-    let mut rng = <SmallRng as SeedableRng>::seed_from_u64(seed);
-    let mut stars = Vec::with_capacity(AMOUNT);
+/// Generates the stars of a seed one after the other. The first stars
+/// of a seed are the same no matter how many are taken.
+pub struct StarGenerator {
+    rng: SmallRng,
+    names: names::NamePool,
+    placer: Placer,
+    seed: u64,
+    /// Stars generated so far, including the ones that were left out
+    index: usize,
+    /// Stars handed out so far
+    count: usize,
+    /// Stars left out in a row, because they didn't fit
+    dropped: usize,
+    /// What the generator was made from, to tell whether it still fits a map
+    source: (NameSource, Placement, f64),
+}
+
+impl StarGenerator {
+    // <SmallRng as SeedableRng>::Seed could be used here to seed from strings
+    /// `scale` is the map size in light-years, for the separation of the placement.
+    pub fn new(seed: u64, names: &NameSource, placement: Placement, scale: f64) -> Self {
+        StarGenerator {
+            rng: <SmallRng as SeedableRng>::seed_from_u64(seed),
+            names: names::NamePool::new(names),
+            placer: Placer::new(placement, scale),
+            seed,
+            index: 0,
+            count: 0,
+            dropped: 0,
+            source: (names.clone(), placement, scale),
+        }
+    }
+
+    /// Whether the next star is star `index` of a map with these settings.
+    pub fn continues(&self, index: usize, seed: u64, names: &NameSource, placement: Placement, scale: f64) -> bool {
+        self.count == index && self.seed == seed && self.source.1 == placement && self.source.2 == scale && self.source.0 == *names
+    }
+}
+
+/// Stars in a row that don't fit before the map counts as full for the separation.
+const FULL: usize = 100;
+
+/// Ends when the map is full, which only happens with a separation.
+impl Iterator for StarGenerator {
+    type Item = Star;
+
+    fn next(&mut self) -> Option<Star> {
+        while self.dropped < FULL {
+            let star = generate_star(&mut self.rng, &mut self.names, &mut self.placer, self.seed, self.index);
+            self.index += 1;
+            match star {
+                Some(star) => {
+                    self.dropped = 0;
+                    self.count += 1;
+                    return Some(star);
+                },
+                None => self.dropped += 1,
+            }
+        }
+        None
+    }
+}
+
+/// Parses a seed the way it is displayed, as hexadecimal with an optional `0x` prefix.
+pub fn parse_seed(seed: &str) -> Result<u64, std::num::ParseIntError> {
+    u64::from_str_radix(seed.strip_prefix("0x").unwrap_or(seed), 16)
+}
+
+/// `None` if there is no room left for the star.
+fn generate_star(rng: &mut impl Rng, names: &mut names::NamePool, placer: &mut Placer, seed: u64, index: usize) -> Option<Star> {
+    let name = names.pick(rng);
+    let mut class = STARCLASSES.choose_weighted(rng, |c| c.1).unwrap().0;
+    let planet_count = PLANET_DISTRIBUTION.sample(rng).round().max(0.0) as u8;
+    let Some(cords) = placer.place(rng, seed) else {
+        names.unpick(&name);
+        return None;
+    };
 
-    for _ in 0..AMOUNT {
-        stars.push(generate_star(&mut rng));
+    // exotic classes, planets and properties draw from their own generators,
+    // so the stars of a seed stay where they were before those were generated
+    let mut exotic_rng = star_rng(seed, index, EXOTIC_SALT);
+    let mut roll = exotic_rng.gen_range(0.0..100.0);
+    for &(exotic, percentage) in EXOTIC_CLASSES {
+        if roll < percentage {
+            class = exotic;
+            break;
+        }
+        roll -= percentage;
     }
-    stars
+    let properties = star_properties(seed, index, class);
+    let companions = star_companions(seed, index, class, &properties);
+    let planets = star_planets(seed, index, planet_count, properties.luminosity);
+
+    Some(Star {name, class, planets, cords, properties, companions})
+}
+
+/// Physical properties of the star at `index` of a seed, the same for a seed, index and class.
+pub fn star_properties(seed: u64, index: usize, class: StarClass) -> Properties {
+    properties::generate_properties(&mut star_rng(seed, index, PROPERTIES_SALT), class)
+}
+
+/// The `count` planets of the star at `index` of a seed.
+pub fn star_planets(seed: u64, index: usize, count: u8, luminosity: f64) -> Vec<Planet> {
+    planets::generate_planets(&mut star_rng(seed, index, PLANETS_SALT), count, luminosity)
+}
+
+/// The other stars in the system of the star at `index` of a seed.
+pub fn star_companions(seed: u64, index: usize, class: StarClass, properties: &Properties) -> Vec<Companion> {
+    companions::generate_companions(&mut star_rng(seed, index, COMPANIONS_SALT), class, properties)
 }
 
-pub fn generate_stars() -> (Vec<Star>, u64) {
-    let seed = random();
-    let stars = generate_stars_with_seed(seed);
+// what each generator of a star is for
+const PLANETS_SALT: u64 = 0;
+const PROPERTIES_SALT: u64 = 0x5851_f42d_4c95_7f2d;
+const EXOTIC_SALT: u64 = 0x94d0_49bb_1331_11eb;
+const COMPANIONS_SALT: u64 = 0x2545_f491_4f6c_dd1d;
 
-    (stars, seed)
+/// A generator of its own for the star at `index`, `salt` tells apart what it is used for.
+fn star_rng(seed: u64, index: usize, salt: u64) -> SmallRng {
+    SmallRng::seed_from_u64(seed ^ (index as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ salt)
 }
 
-fn generate_star(rng: &mut impl Rng) -> Star {
-    let name = *names::NAMES.choose(rng).unwrap();
-    let class = STARCLASSES.choose_weighted(rng, |c| c.1).unwrap().0;
-    let _planets = PLANET_DISTRIBUTION.sample(rng).round() as u8;
-    let cords = rng.r#gen();
-    Star {name, class, _planets, cords}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn left_out_stars_give_their_names_back() {
+        let placement = Placement { separation: 2.0, ..Placement::default() };
+        let stars: Vec<Star> = StarGenerator::new(7, &NameSource::Catalogue, placement, 50.0).collect();
+        // fewer stars fit than there are names in the catalogue, but not by much
+        assert!(stars.len() < names::NAMES.len());
+        assert!(stars.iter().all(|star| names::NAMES.contains(&star.name.as_str())));
+    }
+
+    /// What tells stars apart, `Star` itself can't be compared.
+    fn identities(stars: &[Star]) -> Vec<(String, StarClass, (f64, f64))> {
+        stars.iter().map(|star| (star.name.clone(), star.class, star.cords)).collect()
+    }
+
+    #[test]
+    fn first_stars_dont_depend_on_the_count() {
+        let generate = |count| -> Vec<Star> {
+            StarGenerator::new(0x5eed, &NameSource::Catalogue, Placement::default(), 50.0).take(count).collect()
+        };
+        assert_eq!(identities(&generate(10)), identities(&generate(40)[..10]));
+    }
 }
@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+
+use super::properties::Properties;
+
+/// What kind of object a star is. The ordinary stars are the Harvard spectral classes,
+/// the rest are brown dwarfs, remnants of dead stars and Wolf-Rayet stars.
+///
+/// Stored as the code, so projects from when classes were single letters still open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StarClass {
+    O,
+    B,
+    A,
+    F,
+    G,
+    K,
+    M,
+    /// Brown dwarfs, from the warmest to the coolest
+    L,
+    T,
+    Y,
+    #[serde(rename = "D")]
+    WhiteDwarf,
+    #[serde(rename = "NS")]
+    NeutronStar,
+    #[serde(rename = "BH")]
+    BlackHole,
+    #[serde(rename = "WR")]
+    WolfRayet,
+}
+
+/// How a class is drawn on the map.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Symbol {
+    Dot,
+    /// A dot with a beam on both sides
+    Pulsar,
+    /// A dark disk with a bright ring around it
+    Ring,
+    /// A dot with spikes, for stars blowing off their outer layers
+    Burst,
+}
+
+/// How a class is drawn in monochrome, where only the shape can tell classes apart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glyph {
+    pub outline: Outline,
+    pub fill: Fill,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outline {
+    Circle,
+    /// Five pointed
+    Star,
+    Diamond,
+    Square,
+    Triangle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fill {
+    Solid,
+    Hollow,
+    /// Hollow with a dot in the middle
+    Ringed,
+}
+
+impl StarClass {
+    /// In the order of declaration, so `class as usize` indexes it.
+    pub const ALL: [StarClass; 14] = [
+        StarClass::O,
+        StarClass::B,
+        StarClass::A,
+        StarClass::F,
+        StarClass::G,
+        StarClass::K,
+        StarClass::M,
+        StarClass::L,
+        StarClass::T,
+        StarClass::Y,
+        StarClass::WhiteDwarf,
+        StarClass::NeutronStar,
+        StarClass::BlackHole,
+        StarClass::WolfRayet,
+    ];
+
+    /// Short code as used in spectral types and labels.
+    pub fn code(self) -> &'static str {
+        match self {
+            StarClass::O => "O",
+            StarClass::B => "B",
+            StarClass::A => "A",
+            StarClass::F => "F",
+            StarClass::G => "G",
+            StarClass::K => "K",
+            StarClass::M => "M",
+            StarClass::L => "L",
+            StarClass::T => "T",
+            StarClass::Y => "Y",
+            StarClass::WhiteDwarf => "D",
+            StarClass::NeutronStar => "NS",
+            StarClass::BlackHole => "BH",
+            StarClass::WolfRayet => "WR",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StarClass::O | StarClass::B | StarClass::A | StarClass::F | StarClass::G | StarClass::K | StarClass::M => "star",
+            StarClass::L | StarClass::T | StarClass::Y => "brown dwarf",
+            StarClass::WhiteDwarf => "white dwarf",
+            StarClass::NeutronStar => "neutron star",
+            StarClass::BlackHole => "black hole",
+            StarClass::WolfRayet => "Wolf-Rayet star",
+        }
+    }
+
+    /// Whether the class is one of OBAFGKM, the only ones with a luminosity class.
+    pub fn is_ordinary(self) -> bool {
+        matches!(self, StarClass::O | StarClass::B | StarClass::A | StarClass::F | StarClass::G | StarClass::K | StarClass::M)
+    }
+
+    /// Full spectral type like G2V.
+    pub fn spectral_type(self, properties: &Properties) -> String {
+        let subclass = properties.subclass;
+        match self {
+            class if class.is_ordinary() => format!("{class}{subclass}{}", properties.luminosity_class.name()),
+            StarClass::L | StarClass::T | StarClass::Y => format!("{self}{subclass}"),
+            // hydrogen lines, the most common kind of white dwarf
+            StarClass::WhiteDwarf => format!("DA{subclass}"),
+            // the nitrogen sequence
+            StarClass::WolfRayet => format!("WN{subclass}"),
+            class => class.to_string(),
+        }
+    }
+
+    pub fn symbol(self) -> Symbol {
+        match self {
+            StarClass::NeutronStar => Symbol::Pulsar,
+            StarClass::BlackHole => Symbol::Ring,
+            StarClass::WolfRayet => Symbol::Burst,
+            _ => Symbol::Dot,
+        }
+    }
+
+    /// A different glyph for every class. Stars are circles from G down, hotter ones
+    /// stars and diamonds, brown dwarfs squares and the dead ones triangles.
+    pub fn glyph(self) -> Glyph {
+        let (outline, fill) = match self {
+            StarClass::O => (Outline::Star, Fill::Solid),
+            StarClass::B => (Outline::Star, Fill::Hollow),
+            StarClass::A => (Outline::Diamond, Fill::Solid),
+            StarClass::F => (Outline::Diamond, Fill::Hollow),
+            StarClass::G => (Outline::Circle, Fill::Solid),
+            StarClass::K => (Outline::Circle, Fill::Ringed),
+            StarClass::M => (Outline::Circle, Fill::Hollow),
+            StarClass::L => (Outline::Square, Fill::Solid),
+            StarClass::T => (Outline::Square, Fill::Hollow),
+            StarClass::Y => (Outline::Square, Fill::Ringed),
+            StarClass::WhiteDwarf => (Outline::Triangle, Fill::Ringed),
+            StarClass::NeutronStar => (Outline::Triangle, Fill::Solid),
+            StarClass::BlackHole => (Outline::Triangle, Fill::Hollow),
+            StarClass::WolfRayet => (Outline::Star, Fill::Ringed),
+        };
+        Glyph { outline, fill }
+    }
+}
+
+impl std::fmt::Display for StarClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
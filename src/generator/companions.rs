@@ -0,0 +1,79 @@
+use rand::prelude::*;
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+
+use super::classes::StarClass;
+use super::properties::{generate_properties, Properties};
+use super::{EXOTIC_CLASSES, STARCLASSES};
+
+/// Another star in the same system, orbiting the main star.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Companion {
+    pub class: StarClass,
+    pub properties: Properties,
+    /// Distance to the main star in AU
+    pub separation: f64,
+}
+
+impl Companion {
+    pub fn spectral_type(&self) -> String {
+        self.class.spectral_type(&self.properties)
+    }
+}
+
+/// Chance that a star of `class` has company, heavy stars are rarely alone.
+fn multiple_chance(class: StarClass) -> f64 {
+    match class {
+        StarClass::O | StarClass::WolfRayet => 0.7,
+        StarClass::B | StarClass::A => 0.5,
+        StarClass::F | StarClass::G => 0.45,
+        StarClass::K => 0.35,
+        StarClass::M | StarClass::WhiteDwarf => 0.25,
+        StarClass::L | StarClass::T | StarClass::Y => 0.2,
+        StarClass::BlackHole => 0.1,
+        StarClass::NeutronStar => 0.05,
+    }
+}
+
+/// The other stars in the system of a star of `class`, most systems are binaries.
+pub fn generate_companions(rng: &mut impl Rng, class: StarClass, primary: &Properties) -> Vec<Companion> {
+    if !rng.gen_bool(multiple_chance(class)) {
+        return Vec::new();
+    }
+    let count = match rng.r#gen::<f64>() {
+        roll if roll < 0.75 => 1,
+        roll if roll < 0.95 => 2,
+        _ => 3,
+    };
+
+    // companions are no heavier than the main star, so of no hotter class.
+    // Remnants were heavier stars once, anything can orbit them.
+    let brown_dwarfs: Vec<(StarClass, f64)> = EXOTIC_CLASSES.iter()
+        .copied()
+        .filter(|&(c, _)| matches!(c, StarClass::L | StarClass::T | StarClass::Y))
+        .collect();
+    let classes: Vec<(StarClass, f64)> = match class {
+        StarClass::L | StarClass::T | StarClass::Y => brown_dwarfs.into_iter().skip_while(|&(c, _)| c != class).collect(),
+        _ if class.is_ordinary() => STARCLASSES.iter().copied().skip_while(|&(c, _)| c != class).collect(),
+        _ => STARCLASSES.to_vec(),
+    };
+
+    // separations are spread log-normally around 50 AU, every further companion
+    // orbits the inner ones from far enough away for the system to be stable
+    let mut separation: f64 = 10.0f64.powf(1.7 + 1.5 * rng.sample::<f64, _>(StandardNormal)).clamp(0.01, 20000.0);
+    let mut companions = Vec::with_capacity(count);
+    for _ in 0..count {
+        let companion = classes.choose_weighted(rng, |c| c.1).unwrap().0;
+        let mut properties = generate_properties(rng, companion);
+        // nor hotter within the same class, as far as a few tries go
+        for _ in 0..10 {
+            if companion != class || properties.temperature <= primary.temperature {
+                break;
+            }
+            properties = generate_properties(rng, companion);
+        }
+        companions.push(Companion { class: companion, properties, separation });
+        separation *= rng.gen_range(5.0..20.0);
+    }
+    companions
+}
@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use rand::Rng;
+
+use super::names::NAMES;
+
+/// Letters the chain looks back on
+const ORDER: usize = 2;
+const START: char = '^';
+const END: char = '$';
+
+/// Makes up names by chaining letters the way they follow each other in the built-in names.
+pub struct Markov {
+    /// For the last `ORDER` letters, which letter came next how often
+    transitions: BTreeMap<[char; ORDER], Vec<(char, u32)>>,
+}
+
+pub static STAR_NAMES: LazyLock<Markov> = LazyLock::new(|| Markov::new(NAMES.iter().copied()));
+
+impl Markov {
+    /// Learns from every word of the names, multi-word names would make for run-on words.
+    pub fn new<'a>(names: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut transitions: BTreeMap<[char; ORDER], Vec<(char, u32)>> = BTreeMap::new();
+        for word in names.into_iter().flat_map(str::split_whitespace) {
+            let word = word.to_lowercase();
+            if word.chars().count() < 3 {
+                continue;
+            }
+
+            let mut context = [START; ORDER];
+            for next in word.chars().chain([END]) {
+                let counts = transitions.entry(context).or_default();
+                match counts.iter_mut().find(|(c, _)| *c == next) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((next, 1)),
+                }
+                context.rotate_left(1);
+                context[ORDER - 1] = next;
+            }
+        }
+        Markov { transitions }
+    }
+
+    /// A capitalized name of 4 to 10 letters, `None` when the chain ran too long or short.
+    pub fn generate(&self, rng: &mut impl Rng) -> Option<String> {
+        let mut name = String::new();
+        let mut context = [START; ORDER];
+        loop {
+            let counts = self.transitions.get(&context)?;
+            let total: u32 = counts.iter().map(|(_, count)| count).sum();
+            let mut pick = rng.gen_range(0..total);
+            let next = counts.iter().find(|(_, count)| {
+                let found = pick < *count;
+                pick = pick.saturating_sub(*count);
+                found
+            })?.0;
+
+            if next == END {
+                break;
+            }
+            name.push(next);
+            if name.chars().count() > 10 {
+                return None;
+            }
+            context.rotate_left(1);
+            context[ORDER - 1] = next;
+        }
+
+        if name.chars().count() < 4 {
+            return None;
+        }
+        let mut chars = name.chars();
+        let first = chars.next()?;
+        Some(first.to_uppercase().chain(chars).collect())
+    }
+}
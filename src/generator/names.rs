@@ -1,4 +1,144 @@
-pub static NAMES: &'static [&str] = &[
+use std::collections::HashSet;
+use std::path::Path;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::markov;
+
+/// Where star names come from.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub enum NameSource {
+    /// The IAU names in [`NAMES`]
+    #[default]
+    Catalogue,
+    /// Made up names that sound like the catalogue
+    Generated,
+    Custom(NameList),
+}
+
+impl NameSource {
+    /// Names of the kinds of sources, in the order of [`NameSource::index`]
+    pub const KINDS: [&str; 3] = ["Star catalogue", "Made up", "From file"];
+
+    pub fn index(&self) -> u32 {
+        match self {
+            NameSource::Catalogue => 0,
+            NameSource::Generated => 1,
+            NameSource::Custom(_) => 2,
+        }
+    }
+}
+
+/// Names loaded from a file.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct NameList {
+    /// Name of the file the list came from
+    pub file: String,
+    pub names: Vec<String>,
+    /// Add the names to the catalogue instead of replacing it
+    pub extend: bool,
+}
+
+impl NameList {
+    /// Reads a JSON array of strings, or a text file with one name per line.
+    /// Empty lines and lines starting with `#` are skipped.
+    pub fn load(path: &Path) -> Result<NameList, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| format!("while reading {}: {e}", path.display()))?;
+        let names: Vec<String> = match contents.trim_start().starts_with('[') {
+            true => serde_json::from_str(&contents).map_err(|e| format!("invalid name list: {e}"))?,
+            false => contents.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_owned)
+                .collect(),
+        };
+
+        let mut seen = HashSet::new();
+        let names: Vec<String> = names.into_iter()
+            .map(|name| name.trim().to_owned())
+            .filter(|name| !name.is_empty() && seen.insert(name.clone()))
+            .collect();
+        if names.is_empty() {
+            return Err(format!("{} has no names in it", path.display()));
+        }
+
+        Ok(NameList {
+            file: path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+            names,
+            extend: false,
+        })
+    }
+}
+
+/// Hands out star names without repeating any.
+///
+/// Names from lists are picked like a Fisher-Yates shuffle that stops after each step,
+/// so every pick takes one number from the generator, the same as picking with replacement did.
+pub struct NamePool {
+    generated: bool,
+    names: Vec<String>,
+    picked: usize,
+    used: HashSet<String>,
+}
+
+impl NamePool {
+    pub fn new(source: &NameSource) -> Self {
+        let names = match source {
+            NameSource::Catalogue => NAMES.iter().map(|&name| name.to_owned()).collect(),
+            NameSource::Generated => Vec::new(),
+            NameSource::Custom(list) if list.extend => {
+                let mut names: Vec<String> = NAMES.iter().map(|&name| name.to_owned()).collect();
+                names.extend(list.names.iter().filter(|name| !NAMES.contains(&name.as_str())).cloned());
+                names
+            },
+            NameSource::Custom(list) => list.names.clone(),
+        };
+        NamePool {
+            generated: *source == NameSource::Generated,
+            names,
+            picked: 0,
+            used: HashSet::new(),
+        }
+    }
+
+    /// When no new name can be found, stars get Henry Draper catalogue numbers instead.
+    pub fn pick(&mut self, rng: &mut impl Rng) -> String {
+        if self.generated {
+            for _ in 0..100 {
+                if let Some(name) = markov::STAR_NAMES.generate(rng)
+                    && self.used.insert(name.clone())
+                {
+                    return name;
+                }
+            }
+        } else if self.picked < self.names.len() {
+            let i = rng.gen_range(self.picked..self.names.len());
+            self.names.swap(self.picked, i);
+            self.picked += 1;
+            let name = self.names[self.picked - 1].clone();
+            self.used.insert(name.clone());
+            return name;
+        }
+
+        loop {
+            let name = format!("HD {}", rng.gen_range(1..=359_083));
+            if self.used.insert(name.clone()) {
+                return name;
+            }
+        }
+    }
+
+    /// Puts back the name of the last [`pick`](NamePool::pick), for a star that wasn't made after all.
+    pub fn unpick(&mut self, name: &str) {
+        self.used.remove(name);
+        if self.picked > 0 && self.names[self.picked - 1] == name {
+            self.picked -= 1;
+        }
+    }
+}
+
+pub static NAMES: &[&str] = &[
     "Absolutno",
     "Acamar",
     "Achernar",
@@ -482,3 +622,22 @@ pub static NAMES: &'static [&str] = &[
     "Zubenelhakrabi",
     "Zubeneschamali",
 ];
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn names_never_repeat() {
+        let mut pool = NamePool::new(&NameSource::Catalogue);
+        let mut rng = SmallRng::seed_from_u64(1);
+        // past the end of the catalogue, into the Henry Draper numbers
+        let names: Vec<String> = (0..NAMES.len() + 200).map(|_| pool.pick(&mut rng)).collect();
+        assert!(names.iter().any(|name| name.starts_with("HD ")));
+
+        let unique: HashSet<&String> = names.iter().collect();
+        assert_eq!(unique.len(), names.len());
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+use rand::prelude::*;
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+
+/// The shape stars are spread out in.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub enum Shape {
+    /// Evenly over the whole map
+    #[default]
+    Uniform,
+    /// A bulge in the middle with arms winding out of it
+    Spiral,
+    Elliptical,
+    /// Densely packed around a small core
+    Globular,
+    /// A few loose clusters and some stars in between
+    Clusters,
+    /// A band across the map, like the milky way, split by a dark rift
+    Band,
+}
+
+impl Shape {
+    pub const ALL: [Shape; 6] = [Shape::Uniform, Shape::Spiral, Shape::Elliptical, Shape::Globular, Shape::Clusters, Shape::Band];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Shape::Uniform => "Uniform",
+            Shape::Spiral => "Spiral",
+            Shape::Elliptical => "Elliptical",
+            Shape::Globular => "Globular",
+            Shape::Clusters => "Clusters",
+            Shape::Band => "Band",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Shape> {
+        Shape::ALL.into_iter().find(|shape| shape.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// How stars are placed on the map. Every shape has its own parameters,
+/// they are all kept so switching back and forth doesn't lose them.
+/// Lengths are fractions of the map, angles are in degrees.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Placement {
+    pub shape: Shape,
+    /// Number of spiral arms
+    pub arms: u32,
+    /// How many turns an arm makes from the middle to the rim
+    pub twist: f64,
+    /// How far stars stray from their arm or cluster
+    pub spread: f64,
+    /// 0 is round, towards 1 an elliptical galaxy gets as flat as a line
+    pub flattening: f64,
+    /// Rotation of an elliptical galaxy or band
+    pub angle: f64,
+    /// Radius of the core of a globular cluster
+    pub core: f64,
+    pub clusters: u32,
+    /// Width of the band
+    pub width: f64,
+    /// Width of the rift, as a fraction of the band
+    pub rift: f64,
+    /// Minimum distance between stars in light-years, 0 to allow them on top of each other
+    pub separation: f64,
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Placement {
+            shape: Shape::Uniform,
+            arms: 2,
+            twist: 0.75,
+            spread: 0.02,
+            flattening: 0.4,
+            angle: 30.0,
+            core: 0.05,
+            clusters: 5,
+            width: 0.12,
+            rift: 0.5,
+            separation: 0.0,
+        }
+    }
+}
+
+/// Positions drawn from a shape before giving up on it and placing the star anywhere.
+const SAMPLES: usize = 1000;
+
+impl Placement {
+    /// Picks a position on the map, from 0 to 1 on both axes.
+    /// What all stars share, like where the clusters are, comes from `seed`.
+    pub fn place(&self, rng: &mut impl Rng, seed: u64) -> (f64, f64) {
+        if self.shape == Shape::Uniform {
+            return rng.r#gen();
+        }
+        // positions off the map are thrown away, so shapes can have long tails
+        for _ in 0..SAMPLES {
+            if let Some((x, y)) = self.sample(rng, seed)
+                && (0.0..1.0).contains(&x)
+                && (0.0..1.0).contains(&y)
+            {
+                return (x, y);
+            }
+        }
+        // the shape hardly touches the map, like a huge globular core
+        rng.r#gen()
+    }
+
+    /// A position around the middle of the map, `None` to try again.
+    fn sample(&self, rng: &mut impl Rng, seed: u64) -> Option<(f64, f64)> {
+        match self.shape {
+            Shape::Uniform => Some(rng.r#gen()),
+            Shape::Spiral => {
+                // a tenth of the stars make up the bulge
+                if rng.gen_bool(0.1) {
+                    return Some((0.5 + gauss(rng) * 0.06, 0.5 + gauss(rng) * 0.06));
+                }
+                let arms = self.arms.max(1);
+                let r = -(1.0 - rng.r#gen::<f64>()).ln() * 0.15;
+                let arm = rng.gen_range(0..arms) as f64 * 2.0 * PI / arms as f64;
+                let angle = arm + self.twist * 2.0 * PI * r / 0.5;
+                Some((
+                    0.5 + r * angle.cos() + gauss(rng) * self.spread,
+                    0.5 + r * angle.sin() + gauss(rng) * self.spread,
+                ))
+            },
+            Shape::Elliptical => {
+                let (x, y) = (gauss(rng) * 0.18, gauss(rng) * 0.18 * (1.0 - self.flattening.clamp(0.0, 0.95)));
+                Some(rotate((x, y), self.angle))
+            },
+            Shape::Globular => {
+                // the Plummer profile, most stars are within a few core radii
+                let u: f64 = rng.gen_range(0.0001..1.0);
+                let r = self.core / (u.powf(-2.0 / 3.0) - 1.0).sqrt();
+                let angle = rng.gen_range(0.0..2.0 * PI);
+                Some((0.5 + r * angle.cos(), 0.5 + r * angle.sin()))
+            },
+            Shape::Clusters => {
+                if rng.gen_bool(0.1) {
+                    return Some(rng.r#gen());
+                }
+                let cluster = rng.gen_range(0..self.clusters.max(1)) as u64;
+                let mut cluster_rng = SmallRng::seed_from_u64(seed.rotate_left(32) ^ (cluster + 1).wrapping_mul(0xbf58_476d_1ce4_e5b9));
+                let (x, y): (f64, f64) = cluster_rng.r#gen();
+                let size = self.spread * cluster_rng.gen_range(0.5..1.5);
+                Some((0.1 + 0.8 * x + gauss(rng) * size, 0.1 + 0.8 * y + gauss(rng) * size))
+            },
+            Shape::Band => {
+                if rng.gen_bool(0.15) {
+                    return Some(rng.r#gen());
+                }
+                let across = gauss(rng) * self.width;
+                // most stars in the rift are hidden by dust
+                if across.abs() < self.width * self.rift / 2.0 && rng.gen_bool(0.85) {
+                    return None;
+                }
+                Some(rotate((rng.gen_range(-0.75..0.75), across), self.angle))
+            },
+        }
+    }
+}
+
+/// Places stars one after the other, keeping them apart if the placement has a separation.
+///
+/// This is Poisson disk sampling by dart throwing: positions closer than the separation
+/// to an earlier star are thrown away. Unlike Bridson's algorithm it works with every shape
+/// and stars can be added later without moving the earlier ones. The separation is never
+/// broken, a star that finds no room is left out instead.
+pub struct Placer {
+    placement: Placement,
+    /// Separation in map coordinates
+    distance: f64,
+    /// Placed stars, sorted into cells as large as the separation
+    grid: HashMap<(i64, i64), Vec<(f64, f64)>>,
+}
+
+/// Positions tried for a star before it is left out.
+const TRIES: usize = 50;
+
+impl Placer {
+    /// `scale` is the map size in light-years.
+    pub fn new(placement: Placement, scale: f64) -> Self {
+        Placer {
+            placement,
+            distance: placement.separation / scale,
+            grid: HashMap::new(),
+        }
+    }
+
+    /// Picks a position like [`Placement::place`], `None` when none of the tries
+    /// is far enough from the other stars.
+    pub fn place(&mut self, rng: &mut impl Rng, seed: u64) -> Option<(f64, f64)> {
+        if self.distance <= 0.0 {
+            return Some(self.placement.place(rng, seed));
+        }
+
+        for _ in 0..TRIES {
+            let position = self.placement.place(rng, seed);
+            if self.room(position) >= self.distance {
+                self.grid.entry(self.cell(position)).or_default().push(position);
+                return Some(position);
+            }
+        }
+        None
+    }
+
+    fn cell(&self, (x, y): (f64, f64)) -> (i64, i64) {
+        ((x / self.distance).floor() as i64, (y / self.distance).floor() as i64)
+    }
+
+    /// Distance to the closest star, as far as it matters for the separation.
+    fn room(&self, position: (f64, f64)) -> f64 {
+        let (x, y) = self.cell(position);
+        let mut room = f64::INFINITY;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for other in self.grid.get(&(x + dx, y + dy)).into_iter().flatten() {
+                    room = room.min((other.0 - position.0).hypot(other.1 - position.1));
+                }
+            }
+        }
+        room
+    }
+}
+
+fn gauss(rng: &mut impl Rng) -> f64 {
+    rng.sample(StandardNormal)
+}
+
+/// Rotates a position around the middle of the map and moves it there.
+fn rotate((x, y): (f64, f64), degrees: f64) -> (f64, f64) {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    (0.5 + x * cos - y * sin, 0.5 + x * sin + y * cos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placed_stars_keep_their_distance() {
+        let scale = 50.0;
+        for shape in Shape::ALL {
+            let placement = Placement { shape, separation: 2.0, ..Placement::default() };
+            let mut placer = Placer::new(placement, scale);
+            let mut rng = SmallRng::seed_from_u64(1);
+            // more than fit, so some are left out
+            let placed: Vec<(f64, f64)> = (0..2000).filter_map(|_| placer.place(&mut rng, 1)).collect();
+            assert!(placed.len() < 2000, "{shape:?} fit every star");
+
+            let distance = placement.separation / scale;
+            for (i, a) in placed.iter().enumerate() {
+                for b in &placed[i + 1..] {
+                    assert!((a.0 - b.0).hypot(a.1 - b.1) >= distance, "{shape:?}: {a:?} and {b:?} are too close");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PlanetKind {
+    Rocky,
+    Ocean,
+    GasGiant,
+    IceGiant,
+    IceDwarf,
+}
+
+impl PlanetKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            PlanetKind::Rocky => "rocky",
+            PlanetKind::Ocean => "ocean",
+            PlanetKind::GasGiant => "gas giant",
+            PlanetKind::IceGiant => "ice giant",
+            PlanetKind::IceDwarf => "ice dwarf",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Planet {
+    /// Distance to the star in AU
+    pub orbit: f64,
+    pub kind: PlanetKind,
+    /// Radius in earth radii
+    pub size: f64,
+    pub moons: u8,
+}
+
+impl std::fmt::Display for Planet {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:.2} AU, {}, {:.1} R⊕", self.orbit, self.kind.name(), self.size)?;
+        match self.moons {
+            0 => Ok(()),
+            1 => write!(f, ", 1 moon"),
+            n => write!(f, ", {n} moons"),
+        }
+    }
+}
+
+/// Generates `count` planets from the innermost orbit outwards, around a star of `luminosity` suns.
+pub fn generate_planets(rng: &mut impl Rng, count: u8, luminosity: f64) -> Vec<Planet> {
+    // water stays liquid around 1 AU for the sun and freezes past 2.7 AU,
+    // remnants that hardly shine still get planets close in
+    let habitable = luminosity.sqrt().max(0.02);
+    let frost_line = 2.7 * habitable;
+
+    let mut orbit = rng.gen_range(0.2..0.6) * habitable;
+    let mut planets = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let kind = if orbit < frost_line {
+            match orbit > 0.9 * habitable && orbit < 1.5 * habitable && rng.gen_bool(0.3) {
+                true => PlanetKind::Ocean,
+                false => PlanetKind::Rocky,
+            }
+        } else if orbit < 4.0 * frost_line {
+            match rng.gen_bool(0.6) {
+                true => PlanetKind::GasGiant,
+                false => PlanetKind::IceGiant,
+            }
+        } else {
+            match rng.gen_bool(0.3) {
+                true => PlanetKind::IceGiant,
+                false => PlanetKind::IceDwarf,
+            }
+        };
+
+        let (size, moons) = match kind {
+            PlanetKind::Rocky => (rng.gen_range(0.3..1.8), rng.gen_range(0..=2)),
+            PlanetKind::Ocean => (rng.gen_range(0.8..2.0), rng.gen_range(0..=2)),
+            PlanetKind::GasGiant => (rng.gen_range(6.0..15.0), rng.gen_range(2..=40)),
+            PlanetKind::IceGiant => (rng.gen_range(3.0..5.0), rng.gen_range(1..=15)),
+            PlanetKind::IceDwarf => (rng.gen_range(0.1..0.5), rng.gen_range(0..=3)),
+        };
+
+        planets.push(Planet { orbit, kind, size, moons });
+        orbit *= rng.gen_range(1.4..2.2);
+    }
+    planets
+}
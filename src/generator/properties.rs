@@ -0,0 +1,212 @@
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::classes::StarClass;
+
+/// Temperature of the sun in kelvin
+const SUN_TEMPERATURE: f64 = 5772.0;
+/// Nothing is older than the universe, in billion years
+const MAX_AGE: f64 = 13.5;
+/// Radius of the sun in km
+const SUN_RADIUS: f64 = 695_700.0;
+
+/// Yerkes luminosity class, how far a star has evolved off the main sequence.
+/// Named by their roman numerals, as they are written in spectral types.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum LuminosityClass {
+    Ia,
+    Ib,
+    II,
+    III,
+    IV,
+    #[default]
+    V,
+}
+
+impl LuminosityClass {
+    pub const ALL: [LuminosityClass; 6] = [
+        LuminosityClass::Ia,
+        LuminosityClass::Ib,
+        LuminosityClass::II,
+        LuminosityClass::III,
+        LuminosityClass::IV,
+        LuminosityClass::V,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LuminosityClass::Ia => "Ia",
+            LuminosityClass::Ib => "Ib",
+            LuminosityClass::II => "II",
+            LuminosityClass::III => "III",
+            LuminosityClass::IV => "IV",
+            LuminosityClass::V => "V",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            LuminosityClass::Ia => "luminous supergiant",
+            LuminosityClass::Ib => "supergiant",
+            LuminosityClass::II => "bright giant",
+            LuminosityClass::III => "giant",
+            LuminosityClass::IV => "subgiant",
+            LuminosityClass::V => "main sequence",
+        }
+    }
+
+    /// How common each luminosity class is for a spectral class, in the order of [`LuminosityClass::ALL`].
+    /// Hot stars are young and often still big, cool giants are old stars that swelled up.
+    fn weights(class: StarClass) -> [f64; 6] {
+        match class {
+            StarClass::O | StarClass::B => [2.0, 2.0, 2.0, 6.0, 8.0, 80.0],
+            StarClass::A | StarClass::F => [0.2, 0.3, 0.5, 3.0, 6.0, 90.0],
+            StarClass::G | StarClass::K => [0.2, 0.3, 0.5, 9.0, 5.0, 85.0],
+            _ => [0.1, 0.2, 0.2, 2.5, 0.0, 97.0],
+        }
+    }
+}
+
+/// Physical properties of a star, all relative to the sun unless noted otherwise.
+/// The luminosity class only means something for OBAFGKM stars.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Properties {
+    /// 0 to 9 within the spectral class, 0 is the hottest
+    pub subclass: u8,
+    pub luminosity_class: LuminosityClass,
+    /// 0 for stars saved before they had properties
+    pub mass: f64,
+    pub radius: f64,
+    /// Surface temperature in kelvin
+    pub temperature: f64,
+    pub luminosity: f64,
+    /// Age in billion years
+    pub age: f64,
+}
+
+/// Surface temperatures of each spectral class, from its hottest to its coolest subclass.
+fn temperature_range(class: StarClass) -> (f64, f64) {
+    match class {
+        StarClass::O => (50000.0, 30000.0),
+        StarClass::B => (30000.0, 10000.0),
+        StarClass::A => (10000.0, 7500.0),
+        StarClass::F => (7500.0, 6000.0),
+        StarClass::G => (6000.0, 5200.0),
+        StarClass::K => (5200.0, 3700.0),
+        StarClass::M => (3700.0, 2400.0),
+        StarClass::L => (2400.0, 1300.0),
+        StarClass::T => (1300.0, 550.0),
+        StarClass::Y => (550.0, 250.0),
+        StarClass::WolfRayet => (150000.0, 30000.0),
+        _ => unreachable!("{class} has no subclasses"),
+    }
+}
+
+/// A subclass and a temperature within it, spread evenly on a log scale through the class.
+fn subclass_temperature(rng: &mut impl Rng, class: StarClass) -> (u8, f64) {
+    let subclass = match class {
+        // there are no O0 to O1 or WN0 to WN1 stars
+        StarClass::O | StarClass::WolfRayet => rng.gen_range(2..=9),
+        _ => rng.gen_range(0..=9),
+    };
+    let (hot, cool) = temperature_range(class);
+    let step = (subclass as f64 + rng.r#gen::<f64>()) / 10.0;
+    (subclass, hot * (cool / hot).powf(step))
+}
+
+/// Luminosity of a body of `radius` at `temperature`, with the Stefan-Boltzmann law.
+fn luminosity(radius: f64, temperature: f64) -> f64 {
+    radius.powi(2) * (temperature / SUN_TEMPERATURE).powi(4)
+}
+
+/// Rough main sequence relations: luminosity grows with mass^3.5 and radius with mass^0.8,
+/// so the temperature grows with mass^0.475.
+fn main_sequence_mass(temperature: f64) -> f64 {
+    (temperature / SUN_TEMPERATURE).powf(1.0 / 0.475)
+}
+
+/// Time a star of `mass` spends on the main sequence, in billion years.
+fn main_sequence_lifetime(mass: f64) -> f64 {
+    10.0 * mass.powf(-2.5)
+}
+
+/// Properties of an object of `class`.
+pub fn generate_properties(rng: &mut impl Rng, class: StarClass) -> Properties {
+    match class {
+        StarClass::L | StarClass::T | StarClass::Y => {
+            let (subclass, temperature) = subclass_temperature(rng, class);
+            let mass = match class {
+                StarClass::L => rng.gen_range(0.06..0.08),
+                StarClass::T => rng.gen_range(0.03..0.07),
+                _ => rng.gen_range(0.01..0.03),
+            };
+            // about as big as Jupiter, no matter the mass
+            let radius = rng.gen_range(0.08..0.12);
+            let luminosity = luminosity(radius, temperature);
+            Properties {subclass, mass, radius, temperature, luminosity, age: rng.gen_range(0.5..MAX_AGE), ..Properties::default()}
+        },
+        StarClass::WhiteDwarf => {
+            let temperature = 40000.0 * (4000.0f64 / 40000.0).powf(rng.r#gen());
+            // white dwarf subclasses count down with temperature
+            let subclass = (50400.0 / temperature).round().clamp(1.0, 9.0) as u8;
+            let mass = rng.gen_range(0.5..1.0);
+            // white dwarfs shrink as they get heavier
+            let radius = 0.012 * (0.6f64 / mass).cbrt();
+            let luminosity = luminosity(radius, temperature);
+            // the time to cool down on top of the life of the star it was
+            let age = ((12000.0 / temperature).powf(2.5) + rng.gen_range(0.1..2.0)).min(MAX_AGE);
+            Properties {subclass, mass, radius, temperature, luminosity, age, ..Properties::default()}
+        },
+        StarClass::NeutronStar => {
+            let temperature = 1e5 * 10.0f64.powf(rng.r#gen());
+            let radius = rng.gen_range(10.0..13.0) / SUN_RADIUS;
+            let luminosity = luminosity(radius, temperature);
+            Properties {mass: rng.gen_range(1.2..2.1), radius, temperature, luminosity, age: rng.gen_range(0.001..1.0), ..Properties::default()}
+        },
+        StarClass::BlackHole => {
+            let mass = rng.gen_range(5.0..30.0);
+            // the event horizon, about 3 km per solar mass
+            let radius = 2.95 * mass / SUN_RADIUS;
+            Properties {mass, radius, age: rng.gen_range(0.01..MAX_AGE), ..Properties::default()}
+        },
+        StarClass::WolfRayet => {
+            let (subclass, temperature) = subclass_temperature(rng, class);
+            // the radius follows from how bright it is at this temperature
+            let luminosity = 10.0f64.powf(rng.gen_range(5.0..6.0));
+            let radius = luminosity.sqrt() / (temperature / SUN_TEMPERATURE).powi(2);
+            Properties {subclass, mass: rng.gen_range(10.0..25.0), radius, temperature, luminosity, age: rng.gen_range(0.002..0.008), ..Properties::default()}
+        },
+        _ => ordinary_properties(rng, class),
+    }
+}
+
+/// Properties of an OBAFGKM star, the temperature follows from the class
+/// and everything else from the temperature and how far the star has evolved.
+fn ordinary_properties(rng: &mut impl Rng, class: StarClass) -> Properties {
+    let (subclass, temperature) = subclass_temperature(rng, class);
+
+    let luminosity_class = LuminosityClass::ALL[WeightedIndex::new(LuminosityClass::weights(class)).unwrap().sample(rng)];
+
+    let ms_mass = main_sequence_mass(temperature);
+    let ms_radius = ms_mass.powf(0.8);
+    let (mass, radius) = match luminosity_class {
+        LuminosityClass::V => (ms_mass, ms_radius),
+        LuminosityClass::IV => (ms_mass * rng.gen_range(1.0..1.3), ms_radius * rng.gen_range(1.5..3.0)),
+        LuminosityClass::III => (rng.gen_range(1.0..4.0f64).max(ms_mass), rng.gen_range(8.0..40.0f64).max(ms_radius * 2.0)),
+        LuminosityClass::II => (rng.gen_range(4.0..9.0f64).max(ms_mass), rng.gen_range(40.0..100.0f64).max(ms_radius * 4.0)),
+        LuminosityClass::Ib => (rng.gen_range(9.0..20.0f64).max(ms_mass), rng.gen_range(100.0..300.0f64).max(ms_radius * 8.0)),
+        LuminosityClass::Ia => (rng.gen_range(15.0..40.0f64).max(ms_mass), rng.gen_range(300.0..1000.0f64).max(ms_radius * 16.0)),
+    };
+    let luminosity = luminosity(radius, temperature);
+
+    let lifetime = main_sequence_lifetime(mass);
+    let age = match luminosity_class {
+        LuminosityClass::V => rng.gen_range(0.05..0.95) * lifetime.min(MAX_AGE),
+        // evolved stars have used up the hydrogen in their core
+        _ => lifetime * rng.gen_range(1.0..1.15),
+    }.min(MAX_AGE);
+
+    Properties {subclass, luminosity_class, mass, radius, temperature, luminosity, age}
+}
@@ -0,0 +1,195 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::generator::Star;
+
+/// Which stars are within jump distance of each other.
+/// Every jump is stored once, as a pair of indices with the lower one first.
+#[derive(Debug, Default, Clone)]
+pub struct JumpGraph {
+    pub edges: Vec<(usize, usize)>,
+    neighbours: Vec<Vec<usize>>,
+}
+
+impl JumpGraph {
+    /// Connects all stars closer than `range`, in map coordinates.
+    ///
+    /// Stars are sorted into a grid of `range` sized cells first,
+    /// so only stars in neighbouring cells have to be compared.
+    pub fn new(stars: &[Star], range: f64) -> JumpGraph {
+        let mut graph = JumpGraph {
+            edges: Vec::new(),
+            neighbours: vec![Vec::new(); stars.len()],
+        };
+        if range <= 0.0 {
+            return graph;
+        }
+
+        let cell = |star: &Star| ((star.cords.0 / range).floor() as i64, (star.cords.1 / range).floor() as i64);
+        let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (i, star) in stars.iter().enumerate() {
+            grid.entry(cell(star)).or_default().push(i);
+        }
+
+        for (i, star) in stars.iter().enumerate() {
+            let (x, y) = cell(star);
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    let Some(others) = grid.get(&(x + dx, y + dy)) else {
+                        continue;
+                    };
+                    for &j in others {
+                        if j > i && star.distance(&stars[j]) < range {
+                            graph.edges.push((i, j));
+                        }
+                    }
+                }
+            }
+        }
+
+        graph.edges.sort_unstable();
+        for &(a, b) in &graph.edges {
+            graph.neighbours[a].push(b);
+            graph.neighbours[b].push(a);
+        }
+        graph
+    }
+
+    pub fn neighbours(&self, star: usize) -> &[usize] {
+        self.neighbours.get(star).map(Vec::as_slice).unwrap_or_default()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RouteCost {
+    /// Fewest jumps, the shortest distance among those
+    Jumps,
+    /// Fewest light-years, the fewest jumps among those
+    Distance,
+}
+
+impl RouteCost {
+    pub const ALL: [RouteCost; 2] = [RouteCost::Jumps, RouteCost::Distance];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RouteCost::Jumps => "Fewest jumps",
+            RouteCost::Distance => "Shortest distance",
+        }
+    }
+}
+
+/// Cost of a partial route, compared by its primary cost first.
+#[derive(Clone, Copy, PartialEq)]
+struct Cost(f64, f64);
+
+impl Eq for Cost {}
+
+impl Ord for Cost {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.total_cmp(&other.1))
+    }
+}
+
+impl PartialOrd for Cost {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl JumpGraph {
+    /// Shortest route between two stars with Dijkstra, from `from` to `to` inclusive.
+    /// `None` if `to` can't be reached.
+    pub fn route(&self, stars: &[Star], from: usize, to: usize, cost: RouteCost) -> Option<Vec<usize>> {
+        if from >= self.neighbours.len() || to >= self.neighbours.len() {
+            return None;
+        }
+
+        let mut best = vec![None; self.neighbours.len()];
+        let mut previous = vec![usize::MAX; self.neighbours.len()];
+        let mut queue = BinaryHeap::new();
+        best[from] = Some(Cost(0.0, 0.0));
+        queue.push(Reverse((Cost(0.0, 0.0), from)));
+
+        while let Some(Reverse((Cost(a, b), star))) = queue.pop() {
+            if star == to {
+                break;
+            }
+            if best[star].is_some_and(|best| best < Cost(a, b)) {
+                continue;
+            }
+            for &next in &self.neighbours[star] {
+                let distance = stars[star].distance(&stars[next]);
+                let next_cost = match cost {
+                    RouteCost::Jumps => Cost(a + 1.0, b + distance),
+                    RouteCost::Distance => Cost(a + distance, b + 1.0),
+                };
+                if best[next].is_none_or(|best| next_cost < best) {
+                    best[next] = Some(next_cost);
+                    previous[next] = star;
+                    queue.push(Reverse((next_cost, next)));
+                }
+            }
+        }
+
+        best[to]?;
+        let mut route = vec![to];
+        while *route.last().unwrap() != from {
+            route.push(previous[*route.last().unwrap()]);
+        }
+        route.reverse();
+        Some(route)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::classes::StarClass;
+
+    fn stars(cords: &[(f64, f64)]) -> Vec<Star> {
+        cords.iter().map(|&cords| Star {
+            name: String::new(),
+            class: StarClass::G,
+            planets: Vec::new(),
+            cords,
+            properties: Default::default(),
+            companions: Vec::new(),
+        }).collect()
+    }
+
+    #[test]
+    fn fewest_jumps_or_shortest_distance() {
+        // two long jumps over the top, or three short ones along the bottom
+        let stars = stars(&[(0.0, 0.0), (0.3, 0.15), (0.2, 0.0), (0.4, 0.0), (0.6, 0.0)]);
+        let graph = JumpGraph::new(&stars, 0.35);
+        assert_eq!(graph.route(&stars, 0, 4, RouteCost::Jumps), Some(vec![0, 1, 4]));
+        assert_eq!(graph.route(&stars, 0, 4, RouteCost::Distance), Some(vec![0, 2, 3, 4]));
+    }
+
+    #[test]
+    fn jumps_ties_go_to_the_shorter_route() {
+        let stars = stars(&[(0.0, 0.0), (0.3, 0.15), (0.3, -0.05), (0.6, 0.0)]);
+        let graph = JumpGraph::new(&stars, 0.35);
+        assert_eq!(graph.route(&stars, 0, 3, RouteCost::Jumps), Some(vec![0, 2, 3]));
+    }
+
+    #[test]
+    fn distance_ties_go_to_fewer_jumps() {
+        // all in a row, every route forward is as long as the others
+        let stars = stars(&[(0.0, 0.0), (0.125, 0.0), (0.25, 0.0), (0.375, 0.0), (0.5, 0.0)]);
+        let graph = JumpGraph::new(&stars, 0.3);
+        assert_eq!(graph.route(&stars, 0, 4, RouteCost::Distance), Some(vec![0, 2, 4]));
+    }
+
+    #[test]
+    fn unreachable_stars_have_no_route() {
+        let stars = stars(&[(0.0, 0.0), (0.1, 0.0), (0.9, 0.9)]);
+        let graph = JumpGraph::new(&stars, 0.2);
+        for cost in RouteCost::ALL {
+            assert_eq!(graph.route(&stars, 0, 2, cost), None);
+            assert_eq!(graph.route(&stars, 0, 3, cost), None);
+            assert_eq!(graph.route(&stars, 1, 1, cost), Some(vec![1]));
+        }
+    }
+}
@@ -1,50 +1,99 @@
-use std::fs::File;
+use std::path::PathBuf;
 
-use generator::generate_stars_with_seed;
 use gtk::gdk;
 use gtk::pango;
 use gtk::prelude::*;
 use gtk::FontLevel;
 use relm4::prelude::*;
 use relm4::abstractions::drawing::*;
+mod cli;
 mod draw;
+mod export;
 mod generator;
+mod jumps;
+mod map;
+mod palette;
+mod project;
+mod theme;
 
-use generator::Star;
+use draw::{Corner, OverlayKind, Overlays};
+use export::{Format, ImageSize, PageSetup, Paper};
+use generator::{classes::StarClass, names::{NameList, NameSource}, placement::Shape, Star};
+use jumps::RouteCost;
+use map::Map;
+use palette::Palette;
+use theme::Theme;
 
-#[derive(PartialEq, Clone, Debug)]
-struct Colors {
-    starnames: (f64,f64,f64),
-    wall: (f64,f64,f64),
-    starcolor: Option<(f64,f64,f64)>,
-    jumplines: (f64,f64,f64),
+struct App {
+    map: Map,
+    draw_handler: DrawHandler,
+    page_setup: PageSetup,
+    image_size: ImageSize,
+    /// Export images at the size of the drawing area instead of `image_size`
+    match_window: bool,
+    /// Format the map was last saved in, offered again in the save dialog
+    save_format: Format,
+    /// Text of the seed entry, only replaced when the seed changes so typing isn't undone
+    seed_buffer: gtk::EntryBuffer,
+    /// Seed shown in `seed_buffer`
+    shown_seed: u64,
+    /// Star under the mouse
+    hovered: Option<usize>,
+    /// Star shown in the detail panel
+    selected: Option<usize>,
+    orrery_handler: DrawHandler,
+    route_from: Option<usize>,
+    route_to: Option<usize>,
+    route_cost: RouteCost,
+    /// Last loaded name list, kept when switching to other names
+    name_list: Option<NameList>,
+    themes: Vec<Theme>,
+    /// Names of `themes`, for the dropdown
+    theme_names: gtk::StringList,
+    /// Name to save the current colors under
+    theme_name: String,
+    /// Color for all stars, kept while stars have their class colors
+    star_color: (f64,f64,f64),
+    /// Class whose color is being edited
+    edited_class: StarClass,
+    overlay_rows: [OverlayRow; 4],
 }
 
-static DARK_COLORS: Colors = Colors {
-    starnames: (1.0,1.0,1.0),
-    wall: (0.0,0.0,0.0),
-    starcolor: None,
-    jumplines: (0.5,0.5,0.5),
-};
+/// The switch and corner of an overlay in the Overlays expander.
+struct OverlayRow {
+    kind: OverlayKind,
+    visible: gtk::Switch,
+    visible_handler: gtk::glib::SignalHandlerId,
+    corner: gtk::DropDown,
+    corner_handler: gtk::glib::SignalHandlerId,
+}
 
-static LIGHT_COLORS: Colors = Colors {
-    starnames: (0.0,0.0,0.0),
-    wall: (1.0,1.0,1.0),
-    starcolor: None,
-    jumplines: (0.7,0.7,0.7),
-};
+impl OverlayRow {
+    fn new(kind: OverlayKind, sender: &ComponentSender<App>) -> Self {
+        let visible = gtk::Switch::builder().valign(gtk::Align::Center).build();
+        let visible_handler = visible.connect_active_notify({
+            let sender = sender.clone();
+            move |s| sender.input(Msg::ShowOverlay(kind, s.is_active()))
+        });
+        let corner = gtk::DropDown::from_strings(&Corner::ALL.map(Corner::name));
+        let corner_handler = corner.connect_selected_notify({
+            let sender = sender.clone();
+            move |d| sender.input(Msg::OverlayCorner(kind, Corner::ALL[d.selected() as usize]))
+        });
+        OverlayRow { kind, visible, visible_handler, corner, corner_handler }
+    }
 
-struct App {
-    stars: Vec<Star>,
-    font_desc: pango::FontDescription,
-    starcount: u32,
-    draw_handler: DrawHandler,
-    seed: u64,
-    jumplines: bool,
-    jumpdistance: f64,
-    colors: Colors,
-    scale: f64,
-    display_class: bool,
+    /// Shows the settings of the overlay without sending them back.
+    fn update(&self, overlays: &Overlays) {
+        let overlay = overlays.get(self.kind);
+        self.visible.block_signal(&self.visible_handler);
+        self.visible.set_active(overlay.visible);
+        self.visible.unblock_signal(&self.visible_handler);
+        self.corner.block_signal(&self.corner_handler);
+        self.corner.set_sensitive(overlay.visible);
+        self.corner.set_selected(overlay.corner as u32);
+        self.corner.unblock_signal(&self.corner_handler);
+    }
 }
 
 #[derive(Debug)]
@@ -52,14 +101,73 @@ enum Msg {
     FontSelected(pango::FontDescription),
     StarCountChanged(u32),
     RegenerateSeed,
+    /// Index into [`NameSource::KINDS`]
+    NameSource(u32),
+    LoadNames,
+    LoadNamesFrom(PathBuf),
+    ExtendNames(bool),
+    Shape(Shape),
+    Arms(u32),
+    Twist(f64),
+    Spread(f64),
+    Flattening(f64),
+    ShapeAngle(f64),
+    Core(f64),
+    Clusters(u32),
+    BandWidth(f64),
+    Rift(f64),
+    Separation(f64),
     EditedSeed(String),
-    DarkSelected,
-    LightSelected,
+    /// Index into the themes
+    Theme(u32),
+    WallColor(gdk::RGBA),
+    NameColor(gdk::RGBA),
+    JumplineColor(gdk::RGBA),
+    UniformStarColor(bool),
+    StarColor(gdk::RGBA),
+    Palette(Palette),
+    EditedClass(StarClass),
+    ClassColor(gdk::RGBA),
+    ThemeName(String),
+    SaveTheme,
+    DeleteTheme,
     JumpDistance(f64),
     JumpLines(bool),
     DisplayClass(bool),
+    SpectralType(bool),
+    Glow(bool),
+    Monochrome(bool),
+    MapTitle(String),
+    ShowOverlay(OverlayKind, bool),
+    OverlayCorner(OverlayKind, Corner),
+    MatchWindow(bool),
+    ImageWidth(u32),
+    ImageHeight(u32),
+    Dpi(f64),
+    FitToPaper,
+    Paper(Paper),
+    Landscape(bool),
+    Margin(f64),
+    Bleed(f64),
+    Columns(u32),
+    Rows(u32),
+    Overlap(f64),
     Save,
+    SaveTo(PathBuf),
+    ExportCatalogue,
+    ExportCatalogueTo(PathBuf),
+    OpenProject,
+    OpenProjectFrom(PathBuf),
+    SaveProject,
+    SaveProjectTo(PathBuf),
     Resize(i32, i32),
+    Hover(Option<(f64, f64)>),
+    Click(f64, f64),
+    Deselect,
+    RouteFrom,
+    RouteTo,
+    RouteCost(RouteCost),
+    ClearRoute,
 }
 
 #[relm4::component]
@@ -88,10 +196,65 @@ impl SimpleComponent for App {
                     },
 
                     gtk::SpinButton {
-                        set_adjustment: &gtk::Adjustment::new(model.starcount as f64,0.0,generator::AMOUNT as f64,1.0,1.0,1.0),
+                        set_adjustment: &gtk::Adjustment::new(model.map.starcount as f64,0.0,generator::MAX_STARS as f64,1.0,1.0,1.0),
+                        #[watch]
+                        set_value: model.map.starcount as f64,
                         connect_value_changed[sender] => move |b| { sender.input(Msg::StarCountChanged(b.value() as u32)) },
                     },
 
+                    gtk::Label {
+                        add_css_class: "warning",
+                        set_wrap: true,
+                        #[watch]
+                        set_visible: model.map.missing_stars() > 0,
+                        #[watch]
+                        set_label: &format!("Only {} stars fit {} ly apart", model.map.stars.len(), model.map.placement.separation),
+                    },
+
+                    gtk::Label {
+                        set_label: "Star names",
+                    },
+
+                    gtk::DropDown::from_strings(&NameSource::KINDS) {
+                        #[watch]
+                        #[block_signal(names_handler)]
+                        set_selected: model.map.names.index(),
+                        connect_selected_notify[sender] => move |d| {
+                            sender.input(Msg::NameSource(d.selected()))
+                        } @names_handler,
+                    },
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_spacing: 5,
+                        #[watch]
+                        set_visible: matches!(model.map.names, NameSource::Custom(_)),
+
+                        gtk::Button {
+                            set_tooltip_text: Some("Load a different name list"),
+                            #[watch]
+                            set_label: &model.name_list_label(),
+                            connect_clicked => Msg::LoadNames,
+                        },
+                        gtk::Box {
+                            set_orientation: gtk::Orientation::Horizontal,
+                            set_halign: gtk::Align::Center,
+                            set_spacing: 10,
+
+                            gtk::Label {
+                                set_label: "Add to catalogue",
+                            },
+                            gtk::Switch {
+                                #[watch]
+                                #[block_signal(extend_handler)]
+                                set_active: model.name_list.as_ref().is_some_and(|list| list.extend),
+                                connect_active_notify[sender] => move |s| {
+                                    sender.input(Msg::ExtendNames(s.is_active()))
+                                } @extend_handler,
+                            },
+                        },
+                    },
+
                     gtk::Label {
                         set_label: "Font",
                     },
@@ -102,30 +265,153 @@ impl SimpleComponent for App {
                         set_use_size: false,
                         set_use_font: true,
                         set_font_features: None,
-                        set_font_desc: &model.font_desc,
+                        #[watch]
+                        #[block_signal(font_handler)]
+                        set_font_desc: &model.map.font_desc,
 
                         connect_font_desc_notify[sender] => move |fdb| {
                             sender.input(Msg::FontSelected(fdb.font_desc().unwrap()));
-                        },
+                        } @font_handler,
                     },
 
                     gtk::Label {
-                        set_label: "Color Preset",
+                        set_label: "Colors",
                     },
 
-                    gtk::Box {
-                        set_orientation: gtk::Orientation::Horizontal,
-                        set_halign: gtk::Align::Center,
-                        append: _light_preset = &gtk::ToggleButton {
-                            set_label: "Light",
-                            set_active: model.colors == LIGHT_COLORS,
-                            connect_toggled => Msg::LightSelected,
-                        },
-                        gtk::ToggleButton {
-                            set_label: "Dark",
-                            set_active: model.colors == DARK_COLORS,
-                            set_group: Some(&_light_preset),
-                            connect_toggled => Msg::DarkSelected,
+                    gtk::DropDown {
+                        set_model: Some(&model.theme_names),
+                        #[watch]
+                        #[block_signal(theme_handler)]
+                        set_selected: model.theme_index(),
+                        connect_selected_notify[sender] => move |d| {
+                            sender.input(Msg::Theme(d.selected()))
+                        } @theme_handler,
+                    },
+
+                    gtk::Expander {
+                        set_label: Some("Edit colors"),
+
+                        #[wrap(Some)]
+                        set_child = &gtk::Grid {
+                            set_row_spacing: 5,
+                            set_column_spacing: 10,
+                            set_margin_top: 5,
+
+                            attach[0, 0, 1, 1] = &gtk::Label {
+                                set_label: "Background",
+                                set_halign: gtk::Align::Start,
+                            },
+                            attach[1, 0, 2, 1] = &gtk::ColorDialogButton {
+                                set_dialog: &gtk::ColorDialog::builder().with_alpha(false).build(),
+                                #[watch]
+                                #[block_signal(wall_handler)]
+                                set_rgba: &rgba(model.map.colors.wall),
+                                connect_rgba_notify[sender] => move |b| { sender.input(Msg::WallColor(b.rgba())) } @wall_handler,
+                            },
+                            attach[0, 1, 1, 1] = &gtk::Label {
+                                set_label: "Star names",
+                                set_halign: gtk::Align::Start,
+                            },
+                            attach[1, 1, 2, 1] = &gtk::ColorDialogButton {
+                                set_dialog: &gtk::ColorDialog::builder().with_alpha(false).build(),
+                                #[watch]
+                                #[block_signal(names_color_handler)]
+                                set_rgba: &rgba(model.map.colors.starnames),
+                                connect_rgba_notify[sender] => move |b| { sender.input(Msg::NameColor(b.rgba())) } @names_color_handler,
+                            },
+                            attach[0, 2, 1, 1] = &gtk::Label {
+                                set_label: "Jumplines",
+                                set_halign: gtk::Align::Start,
+                            },
+                            attach[1, 2, 2, 1] = &gtk::ColorDialogButton {
+                                set_dialog: &gtk::ColorDialog::builder().with_alpha(false).build(),
+                                #[watch]
+                                #[block_signal(jumplines_color_handler)]
+                                set_rgba: &rgba(model.map.colors.jumplines),
+                                connect_rgba_notify[sender] => move |b| { sender.input(Msg::JumplineColor(b.rgba())) } @jumplines_color_handler,
+                            },
+                            attach[0, 3, 1, 1] = &gtk::Label {
+                                set_label: "Star color",
+                                set_halign: gtk::Align::Start,
+                                set_tooltip_text: Some("One color for all stars instead of the color of their class"),
+                            },
+                            attach[1, 3, 1, 1] = &gtk::Switch {
+                                set_valign: gtk::Align::Center,
+                                #[watch]
+                                #[block_signal(uniform_handler)]
+                                set_active: model.map.colors.starcolor.is_some(),
+                                connect_active_notify[sender] => move |s| { sender.input(Msg::UniformStarColor(s.is_active())) } @uniform_handler,
+                            },
+                            attach[2, 3, 1, 1] = &gtk::ColorDialogButton {
+                                set_dialog: &gtk::ColorDialog::builder().with_alpha(false).build(),
+                                #[watch]
+                                set_sensitive: model.map.colors.starcolor.is_some(),
+                                #[watch]
+                                #[block_signal(star_color_handler)]
+                                set_rgba: &rgba(model.star_color),
+                                connect_rgba_notify[sender] => move |b| { sender.input(Msg::StarColor(b.rgba())) } @star_color_handler,
+                            },
+                            attach[0, 4, 1, 1] = &gtk::Label {
+                                set_label: "Class colors",
+                                set_halign: gtk::Align::Start,
+                            },
+                            attach[1, 4, 2, 1] = &gtk::DropDown::from_strings(&Palette::ALL.map(Palette::name)) {
+                                #[watch]
+                                set_sensitive: model.map.colors.starcolor.is_none(),
+                                #[watch]
+                                #[block_signal(palette_handler)]
+                                set_selected: Palette::of(&model.map.colors.classes).map_or(gtk::INVALID_LIST_POSITION, |palette| palette as u32),
+                                connect_selected_notify[sender] => move |d| {
+                                    if let Some(&palette) = Palette::ALL.get(d.selected() as usize) {
+                                        sender.input(Msg::Palette(palette))
+                                    }
+                                } @palette_handler,
+                            },
+                            attach[0, 5, 2, 1] = &gtk::DropDown::from_strings(&StarClass::ALL.map(StarClass::code)) {
+                                set_tooltip_text: Some("Class to change the color of"),
+                                #[watch]
+                                set_sensitive: model.map.colors.starcolor.is_none(),
+                                #[watch]
+                                #[block_signal(edited_class_handler)]
+                                set_selected: model.edited_class as u32,
+                                connect_selected_notify[sender] => move |d| {
+                                    sender.input(Msg::EditedClass(StarClass::ALL[d.selected() as usize]))
+                                } @edited_class_handler,
+                            },
+                            attach[2, 5, 1, 1] = &gtk::ColorDialogButton {
+                                set_dialog: &gtk::ColorDialog::builder().with_alpha(false).build(),
+                                #[watch]
+                                set_sensitive: model.map.colors.starcolor.is_none(),
+                                #[watch]
+                                #[block_signal(class_color_handler)]
+                                set_rgba: &rgba(model.map.colors.classes.get(model.edited_class)),
+                                connect_rgba_notify[sender] => move |b| { sender.input(Msg::ClassColor(b.rgba())) } @class_color_handler,
+                            },
+                            attach[0, 6, 3, 1] = &gtk::Entry {
+                                set_placeholder_text: Some("Theme name"),
+                                #[watch]
+                                #[block_signal(theme_name_handler)]
+                                set_text: &model.theme_name,
+                                connect_changed[sender] => move |e| { sender.input(Msg::ThemeName(e.text().into())) } @theme_name_handler,
+                            },
+                            attach[0, 7, 3, 1] = &gtk::Box {
+                                set_orientation: gtk::Orientation::Horizontal,
+                                set_halign: gtk::Align::Center,
+                                set_spacing: 5,
+
+                                gtk::Button {
+                                    set_label: "Save theme",
+                                    #[watch]
+                                    set_sensitive: !model.theme_name.trim().is_empty(),
+                                    connect_clicked => Msg::SaveTheme,
+                                },
+                                gtk::Button {
+                                    set_label: "Delete",
+                                    #[watch]
+                                    set_sensitive: theme::find(&model.themes, model.theme_name.trim()).is_some_and(|theme| !theme.builtin),
+                                    connect_clicked => Msg::DeleteTheme,
+                                },
+                            },
                         },
                     },
 
@@ -138,14 +424,16 @@ impl SimpleComponent for App {
                         set_spacing: 10,
 
                         gtk::SpinButton {
-                            set_adjustment: &gtk::Adjustment::new(model.jumpdistance, 0.0, 100.0, 0.2, 0.1, 0.0),
+                            set_adjustment: &gtk::Adjustment::new(model.map.jumpdistance, 0.0, 100.0, 0.2, 0.1, 0.0),
                             set_digits: 2,
+                            #[watch]
+                            set_value: model.map.jumpdistance,
                             set_width_request: 150,
                             connect_value_changed[sender] => move |b| { sender.input(Msg::JumpDistance(b.value())) },
                         },
                         gtk::Switch {
                             #[watch]
-                            set_active: model.jumplines,
+                            set_active: model.map.jumplines,
                             connect_active_notify[sender] => move |s| { sender.input(Msg::JumpLines(s.is_active())) },
                         },
                     },
@@ -160,14 +448,466 @@ impl SimpleComponent for App {
                         },
                         gtk::Switch {
                             #[watch]
-                            set_active: model.display_class,
+                            set_active: model.map.display_class,
                             connect_active_notify[sender] => move |s| { sender.input(Msg::DisplayClass(s.is_active())) },
                         },
                     },
 
-                    gtk::Button {
-                        set_label: "Save",
-                        connect_clicked => Msg::Save,
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_halign: gtk::Align::Center,
+                        set_spacing: 10,
+
+                        gtk::Label {
+                            set_label: "Full spectral type",
+                        },
+                        gtk::Switch {
+                            set_tooltip_text: Some("Show G2V instead of G"),
+                            #[watch]
+                            set_sensitive: model.map.display_class,
+                            #[watch]
+                            set_active: model.map.spectral_type,
+                            connect_active_notify[sender] => move |s| { sender.input(Msg::SpectralType(s.is_active())) },
+                        },
+                    },
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_halign: gtk::Align::Center,
+                        set_spacing: 10,
+
+                        gtk::Label {
+                            set_label: "Glow around bright stars",
+                        },
+                        gtk::Switch {
+                            #[watch]
+                            set_active: model.map.glow,
+                            connect_active_notify[sender] => move |s| { sender.input(Msg::Glow(s.is_active())) },
+                        },
+                    },
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_halign: gtk::Align::Center,
+                        set_spacing: 10,
+
+                        gtk::Label {
+                            set_label: "Monochrome",
+                        },
+                        gtk::Switch {
+                            set_tooltip_text: Some("Draw star classes as glyphs with a legend, for black and white printers"),
+                            #[watch]
+                            set_active: model.map.monochrome,
+                            connect_active_notify[sender] => move |s| { sender.input(Msg::Monochrome(s.is_active())) },
+                        },
+                    },
+
+                    gtk::Expander {
+                        set_label: Some("Overlays"),
+
+                        // a row for every overlay is added in init
+                        #[wrap(Some)]
+                        set_child: overlay_grid = &gtk::Grid {
+                            set_row_spacing: 5,
+                            set_column_spacing: 10,
+                            set_margin_top: 5,
+
+                            attach[0, 0, 3, 1] = &gtk::Entry {
+                                set_placeholder_text: Some("Map title"),
+                                #[watch]
+                                #[block_signal(title_handler)]
+                                set_text: &model.map.title,
+                                connect_changed[sender] => move |e| { sender.input(Msg::MapTitle(e.text().into())) } @title_handler,
+                            },
+                        },
+                    },
+
+                    gtk::Expander {
+                        set_label: Some("Galaxy shape"),
+
+                        #[wrap(Some)]
+                        set_child = &gtk::Box {
+                            set_orientation: gtk::Orientation::Vertical,
+                            set_spacing: 5,
+                            set_margin_top: 5,
+
+                            gtk::DropDown::from_strings(&Shape::ALL.map(Shape::name)) {
+                                #[watch]
+                                #[block_signal(shape_handler)]
+                                set_selected: model.map.placement.shape as u32,
+                                connect_selected_notify[sender] => move |d| {
+                                    sender.input(Msg::Shape(Shape::ALL[d.selected() as usize]))
+                                } @shape_handler,
+                            },
+                            gtk::Box {
+                                set_orientation: gtk::Orientation::Horizontal,
+                                set_spacing: 10,
+
+                                gtk::Label {
+                                    set_label: "Min. distance (ly)",
+                                    set_tooltip_text: Some("Keep stars at least this far apart"),
+                                },
+                                gtk::SpinButton {
+                                    set_adjustment: &gtk::Adjustment::new(model.map.placement.separation, 0.0, 20.0, 0.1, 1.0, 0.0),
+                                    set_digits: 1,
+                                    #[watch]
+                                    #[block_signal(separation_handler)]
+                                    set_value: model.map.placement.separation,
+                                    connect_value_changed[sender] => move |b| { sender.input(Msg::Separation(b.value())) } @separation_handler,
+                                },
+                            },
+                            gtk::Grid {
+                                set_row_spacing: 5,
+                                set_column_spacing: 10,
+                                #[watch]
+                                set_visible: model.map.placement.shape == Shape::Spiral,
+
+                                attach[0, 0, 1, 1] = &gtk::Label {
+                                    set_label: "Arms",
+                                    set_halign: gtk::Align::Start,
+                                },
+                                attach[1, 0, 1, 1] = &gtk::SpinButton {
+                                    set_adjustment: &gtk::Adjustment::new(model.map.placement.arms as f64, 1.0, 12.0, 1.0, 1.0, 0.0),
+                                    #[watch]
+                                    #[block_signal(arms_handler)]
+                                    set_value: model.map.placement.arms as f64,
+                                    connect_value_changed[sender] => move |b| { sender.input(Msg::Arms(b.value() as u32)) } @arms_handler,
+                                },
+                                attach[0, 1, 1, 1] = &gtk::Label {
+                                    set_label: "Twist (turns)",
+                                    set_halign: gtk::Align::Start,
+                                },
+                                attach[1, 1, 1, 1] = &gtk::SpinButton {
+                                    set_adjustment: &gtk::Adjustment::new(model.map.placement.twist, 0.0, 3.0, 0.05, 0.25, 0.0),
+                                    set_digits: 2,
+                                    #[watch]
+                                    #[block_signal(twist_handler)]
+                                    set_value: model.map.placement.twist,
+                                    connect_value_changed[sender] => move |b| { sender.input(Msg::Twist(b.value())) } @twist_handler,
+                                },
+                                attach[0, 2, 1, 1] = &gtk::Label {
+                                    set_label: "Spread",
+                                    set_halign: gtk::Align::Start,
+                                },
+                                attach[1, 2, 1, 1] = &gtk::SpinButton {
+                                    set_adjustment: &gtk::Adjustment::new(model.map.placement.spread, 0.0, 0.3, 0.01, 0.05, 0.0),
+                                    set_digits: 2,
+                                    #[watch]
+                                    #[block_signal(spiral_spread_handler)]
+                                    set_value: model.map.placement.spread,
+                                    connect_value_changed[sender] => move |b| { sender.input(Msg::Spread(b.value())) } @spiral_spread_handler,
+                                },
+                            },
+                            gtk::Grid {
+                                set_row_spacing: 5,
+                                set_column_spacing: 10,
+                                #[watch]
+                                set_visible: model.map.placement.shape == Shape::Elliptical,
+
+                                attach[0, 0, 1, 1] = &gtk::Label {
+                                    set_label: "Flattening",
+                                    set_halign: gtk::Align::Start,
+                                },
+                                attach[1, 0, 1, 1] = &gtk::SpinButton {
+                                    set_adjustment: &gtk::Adjustment::new(model.map.placement.flattening, 0.0, 0.9, 0.05, 0.1, 0.0),
+                                    set_digits: 2,
+                                    #[watch]
+                                    #[block_signal(flattening_handler)]
+                                    set_value: model.map.placement.flattening,
+                                    connect_value_changed[sender] => move |b| { sender.input(Msg::Flattening(b.value())) } @flattening_handler,
+                                },
+                                attach[0, 1, 1, 1] = &gtk::Label {
+                                    set_label: "Angle (°)",
+                                    set_halign: gtk::Align::Start,
+                                },
+                                attach[1, 1, 1, 1] = &gtk::SpinButton {
+                                    set_adjustment: &gtk::Adjustment::new(model.map.placement.angle, -180.0, 180.0, 1.0, 15.0, 0.0),
+                                    #[watch]
+                                    #[block_signal(elliptical_angle_handler)]
+                                    set_value: model.map.placement.angle,
+                                    connect_value_changed[sender] => move |b| { sender.input(Msg::ShapeAngle(b.value())) } @elliptical_angle_handler,
+                                },
+                            },
+                            gtk::Grid {
+                                set_row_spacing: 5,
+                                set_column_spacing: 10,
+                                #[watch]
+                                set_visible: model.map.placement.shape == Shape::Globular,
+
+                                attach[0, 0, 1, 1] = &gtk::Label {
+                                    set_label: "Core radius",
+                                    set_halign: gtk::Align::Start,
+                                },
+                                attach[1, 0, 1, 1] = &gtk::SpinButton {
+                                    set_adjustment: &gtk::Adjustment::new(model.map.placement.core, 0.005, 0.3, 0.005, 0.05, 0.0),
+                                    set_digits: 3,
+                                    #[watch]
+                                    #[block_signal(core_handler)]
+                                    set_value: model.map.placement.core,
+                                    connect_value_changed[sender] => move |b| { sender.input(Msg::Core(b.value())) } @core_handler,
+                                },
+                            },
+                            gtk::Grid {
+                                set_row_spacing: 5,
+                                set_column_spacing: 10,
+                                #[watch]
+                                set_visible: model.map.placement.shape == Shape::Clusters,
+
+                                attach[0, 0, 1, 1] = &gtk::Label {
+                                    set_label: "Clusters",
+                                    set_halign: gtk::Align::Start,
+                                },
+                                attach[1, 0, 1, 1] = &gtk::SpinButton {
+                                    set_adjustment: &gtk::Adjustment::new(model.map.placement.clusters as f64, 1.0, 50.0, 1.0, 5.0, 0.0),
+                                    #[watch]
+                                    #[block_signal(clusters_handler)]
+                                    set_value: model.map.placement.clusters as f64,
+                                    connect_value_changed[sender] => move |b| { sender.input(Msg::Clusters(b.value() as u32)) } @clusters_handler,
+                                },
+                                attach[0, 1, 1, 1] = &gtk::Label {
+                                    set_label: "Spread",
+                                    set_halign: gtk::Align::Start,
+                                },
+                                attach[1, 1, 1, 1] = &gtk::SpinButton {
+                                    set_adjustment: &gtk::Adjustment::new(model.map.placement.spread, 0.0, 0.3, 0.01, 0.05, 0.0),
+                                    set_digits: 2,
+                                    #[watch]
+                                    #[block_signal(clusters_spread_handler)]
+                                    set_value: model.map.placement.spread,
+                                    connect_value_changed[sender] => move |b| { sender.input(Msg::Spread(b.value())) } @clusters_spread_handler,
+                                },
+                            },
+                            gtk::Grid {
+                                set_row_spacing: 5,
+                                set_column_spacing: 10,
+                                #[watch]
+                                set_visible: model.map.placement.shape == Shape::Band,
+
+                                attach[0, 0, 1, 1] = &gtk::Label {
+                                    set_label: "Width",
+                                    set_halign: gtk::Align::Start,
+                                },
+                                attach[1, 0, 1, 1] = &gtk::SpinButton {
+                                    set_adjustment: &gtk::Adjustment::new(model.map.placement.width, 0.01, 0.5, 0.01, 0.05, 0.0),
+                                    set_digits: 2,
+                                    #[watch]
+                                    #[block_signal(width_handler)]
+                                    set_value: model.map.placement.width,
+                                    connect_value_changed[sender] => move |b| { sender.input(Msg::BandWidth(b.value())) } @width_handler,
+                                },
+                                attach[0, 1, 1, 1] = &gtk::Label {
+                                    set_label: "Rift",
+                                    set_halign: gtk::Align::Start,
+                                },
+                                attach[1, 1, 1, 1] = &gtk::SpinButton {
+                                    set_adjustment: &gtk::Adjustment::new(model.map.placement.rift, 0.0, 1.0, 0.05, 0.1, 0.0),
+                                    set_digits: 2,
+                                    #[watch]
+                                    #[block_signal(rift_handler)]
+                                    set_value: model.map.placement.rift,
+                                    connect_value_changed[sender] => move |b| { sender.input(Msg::Rift(b.value())) } @rift_handler,
+                                },
+                                attach[0, 2, 1, 1] = &gtk::Label {
+                                    set_label: "Angle (°)",
+                                    set_halign: gtk::Align::Start,
+                                },
+                                attach[1, 2, 1, 1] = &gtk::SpinButton {
+                                    set_adjustment: &gtk::Adjustment::new(model.map.placement.angle, -180.0, 180.0, 1.0, 15.0, 0.0),
+                                    #[watch]
+                                    #[block_signal(band_angle_handler)]
+                                    set_value: model.map.placement.angle,
+                                    connect_value_changed[sender] => move |b| { sender.input(Msg::ShapeAngle(b.value())) } @band_angle_handler,
+                                },
+                            },
+                        },
+                    },
+
+                    gtk::Expander {
+                        set_label: Some("Route"),
+
+                        #[wrap(Some)]
+                        set_child = &gtk::Box {
+                            set_orientation: gtk::Orientation::Vertical,
+                            set_spacing: 5,
+                            set_margin_top: 5,
+
+                            gtk::DropDown::from_strings(&RouteCost::ALL.map(RouteCost::name)) {
+                                set_selected: model.route_cost as u32,
+                                connect_selected_notify[sender] => move |d| { sender.input(Msg::RouteCost(RouteCost::ALL[d.selected() as usize])) },
+                            },
+                            gtk::Label {
+                                set_halign: gtk::Align::Start,
+                                set_wrap: true,
+                                #[watch]
+                                set_label: &model.route_description(),
+                            },
+                            gtk::Button {
+                                set_label: "Clear route",
+                                connect_clicked => Msg::ClearRoute,
+                            },
+                        },
+                    },
+
+                    gtk::Expander {
+                        set_label: Some("Image size (PNG, SVG)"),
+
+                        #[wrap(Some)]
+                        set_child = &gtk::Grid {
+                            set_row_spacing: 5,
+                            set_column_spacing: 10,
+                            set_margin_top: 5,
+
+                            attach[0, 0, 1, 1] = &gtk::Label {
+                                set_label: "Same as window",
+                                set_halign: gtk::Align::Start,
+                            },
+                            attach[1, 0, 1, 1] = &gtk::Switch {
+                                set_halign: gtk::Align::Start,
+                                set_active: model.match_window,
+                                connect_active_notify[sender] => move |s| { sender.input(Msg::MatchWindow(s.is_active())) },
+                            },
+                            attach[0, 1, 1, 1] = &gtk::Label {
+                                set_label: "Width (px)",
+                                set_halign: gtk::Align::Start,
+                            },
+                            attach[1, 1, 1, 1] = &gtk::SpinButton {
+                                set_adjustment: &gtk::Adjustment::new(model.image_size.width as f64, 1.0, 30000.0, 1.0, 100.0, 0.0),
+                                #[watch]
+                                set_value: model.image_size.width as f64,
+                                #[watch]
+                                set_sensitive: !model.match_window,
+                                connect_value_changed[sender] => move |b| { sender.input(Msg::ImageWidth(b.value() as u32)) },
+                            },
+                            attach[0, 2, 1, 1] = &gtk::Label {
+                                set_label: "Height (px)",
+                                set_halign: gtk::Align::Start,
+                            },
+                            attach[1, 2, 1, 1] = &gtk::SpinButton {
+                                set_adjustment: &gtk::Adjustment::new(model.image_size.height as f64, 1.0, 30000.0, 1.0, 100.0, 0.0),
+                                #[watch]
+                                set_value: model.image_size.height as f64,
+                                #[watch]
+                                set_sensitive: !model.match_window,
+                                connect_value_changed[sender] => move |b| { sender.input(Msg::ImageHeight(b.value() as u32)) },
+                            },
+                            attach[0, 3, 1, 1] = &gtk::Label {
+                                set_label: "DPI",
+                                set_halign: gtk::Align::Start,
+                            },
+                            attach[1, 3, 1, 1] = &gtk::SpinButton {
+                                set_adjustment: &gtk::Adjustment::new(model.image_size.dpi, 24.0, 1200.0, 1.0, 50.0, 0.0),
+                                #[watch]
+                                set_sensitive: !model.match_window,
+                                connect_value_changed[sender] => move |b| { sender.input(Msg::Dpi(b.value())) },
+                            },
+                            attach[0, 4, 2, 1] = &gtk::Button {
+                                set_label: "Size of print layout",
+                                set_tooltip_text: Some("Use the size of the PDF print layout at this DPI"),
+                                #[watch]
+                                set_sensitive: !model.match_window,
+                                connect_clicked => Msg::FitToPaper,
+                            },
+                        },
+                    },
+
+                    gtk::Expander {
+                        set_label: Some("Print layout (PDF)"),
+
+                        #[wrap(Some)]
+                        set_child = &gtk::Grid {
+                            set_row_spacing: 5,
+                            set_column_spacing: 10,
+                            set_margin_top: 5,
+
+                            attach[0, 0, 1, 1] = &gtk::Label {
+                                set_label: "Paper",
+                                set_halign: gtk::Align::Start,
+                            },
+                            attach[1, 0, 1, 1] = &gtk::DropDown::from_strings(&Paper::ALL.map(Paper::name)) {
+                                set_selected: model.page_setup.paper as u32,
+                                connect_selected_notify[sender] => move |d| { sender.input(Msg::Paper(Paper::ALL[d.selected() as usize])) },
+                            },
+                            attach[0, 1, 1, 1] = &gtk::Label {
+                                set_label: "Landscape",
+                                set_halign: gtk::Align::Start,
+                            },
+                            attach[1, 1, 1, 1] = &gtk::Switch {
+                                set_halign: gtk::Align::Start,
+                                set_active: model.page_setup.landscape,
+                                connect_active_notify[sender] => move |s| { sender.input(Msg::Landscape(s.is_active())) },
+                            },
+                            attach[0, 2, 1, 1] = &gtk::Label {
+                                set_label: "Margin (mm)",
+                                set_halign: gtk::Align::Start,
+                            },
+                            attach[1, 2, 1, 1] = &gtk::SpinButton {
+                                set_adjustment: &gtk::Adjustment::new(model.page_setup.margin, 0.0, 100.0, 1.0, 5.0, 0.0),
+                                connect_value_changed[sender] => move |b| { sender.input(Msg::Margin(b.value())) },
+                            },
+                            attach[0, 3, 1, 1] = &gtk::Label {
+                                set_label: "Bleed (mm)",
+                                set_halign: gtk::Align::Start,
+                            },
+                            attach[1, 3, 1, 1] = &gtk::SpinButton {
+                                set_adjustment: &gtk::Adjustment::new(model.page_setup.bleed, 0.0, 20.0, 1.0, 1.0, 0.0),
+                                connect_value_changed[sender] => move |b| { sender.input(Msg::Bleed(b.value())) },
+                            },
+                            attach[0, 4, 1, 1] = &gtk::Label {
+                                set_label: "Pages across",
+                                set_halign: gtk::Align::Start,
+                            },
+                            attach[1, 4, 1, 1] = &gtk::SpinButton {
+                                set_adjustment: &gtk::Adjustment::new(model.page_setup.columns as f64, 1.0, 10.0, 1.0, 1.0, 0.0),
+                                connect_value_changed[sender] => move |b| { sender.input(Msg::Columns(b.value() as u32)) },
+                            },
+                            attach[0, 5, 1, 1] = &gtk::Label {
+                                set_label: "Pages down",
+                                set_halign: gtk::Align::Start,
+                            },
+                            attach[1, 5, 1, 1] = &gtk::SpinButton {
+                                set_adjustment: &gtk::Adjustment::new(model.page_setup.rows as f64, 1.0, 10.0, 1.0, 1.0, 0.0),
+                                connect_value_changed[sender] => move |b| { sender.input(Msg::Rows(b.value() as u32)) },
+                            },
+                            attach[0, 6, 1, 1] = &gtk::Label {
+                                set_label: "Overlap (mm)",
+                                set_halign: gtk::Align::Start,
+                            },
+                            attach[1, 6, 1, 1] = &gtk::SpinButton {
+                                set_adjustment: &gtk::Adjustment::new(model.page_setup.overlap, 0.0, 50.0, 1.0, 5.0, 0.0),
+                                connect_value_changed[sender] => move |b| { sender.input(Msg::Overlap(b.value())) },
+                            },
+                        },
+                    },
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_halign: gtk::Align::Center,
+                        set_spacing: 5,
+
+                        gtk::Button {
+                            set_label: "Save",
+                            connect_clicked => Msg::Save,
+                        },
+                        gtk::Button {
+                            set_label: "Export catalogue",
+                            set_tooltip_text: Some("Save the visible stars as CSV or JSON"),
+                            connect_clicked => Msg::ExportCatalogue,
+                        },
+                    },
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_halign: gtk::Align::Center,
+                        set_spacing: 5,
+
+                        gtk::Button {
+                            set_label: "Open project",
+                            connect_clicked => Msg::OpenProject,
+                        },
+                        gtk::Button {
+                            set_label: "Save project",
+                            connect_clicked => Msg::SaveProject,
+                        },
                     },
 
                     gtk::Box {
@@ -181,9 +921,7 @@ impl SimpleComponent for App {
 
                         gtk::Entry {
                             gtk::prelude::EditableExt::set_alignment: 0.5,
-                            // https://stackoverflow.com/a/68107365/8935250
-                            #[watch]
-                            set_buffer: &gtk::EntryBuffer::builder().text(format!("{:#x}", model.seed)).build(),
+                            set_buffer: &model.seed_buffer,
                             connect_activate[sender] => move |buf| { sender.input(Msg::EditedSeed(buf.text().to_string())) },
                         },
 
@@ -204,6 +942,67 @@ impl SimpleComponent for App {
                     set_margin_all: 10,
                     set_cursor: gdk::Cursor::from_name("cell", None).as_ref(),
                     connect_resize[sender] => move |_,x,y| {sender.input(Msg::Resize(x,y))},
+                    #[watch]
+                    set_tooltip_text: model.tooltip().as_deref(),
+
+                    add_controller = gtk::EventControllerMotion {
+                        connect_motion[sender] => move |_, x, y| { sender.input(Msg::Hover(Some((x, y)))) },
+                        connect_leave[sender] => move |_| { sender.input(Msg::Hover(None)) },
+                    },
+                    add_controller = gtk::GestureClick {
+                        connect_pressed[sender] => move |_, _, x, y| { sender.input(Msg::Click(x, y)) },
+                    },
+                },
+
+                gtk::Box {
+                    set_width_request: 250,
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_margin_all: 20,
+                    set_spacing: 5,
+                    #[watch]
+                    set_visible: model.selected_star().is_some(),
+
+                    gtk::Label {
+                        set_use_markup: true,
+                        set_halign: gtk::Align::Start,
+                        #[watch]
+                        set_label: &model.star_details(),
+                    },
+
+                    #[local_ref]
+                    _orrery_area -> gtk::DrawingArea {
+                        set_width_request: 220,
+                        set_height_request: 220,
+                        connect_resize[sender] => move |_,x,y| {sender.input(Msg::Resize(x,y))},
+                    },
+
+                    gtk::Label {
+                        set_halign: gtk::Align::Start,
+                        set_valign: gtk::Align::Start,
+                        set_vexpand: true,
+                        #[watch]
+                        set_label: &model.selected_star().map(planet_list).unwrap_or_default(),
+                    },
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_halign: gtk::Align::Center,
+                        set_spacing: 5,
+
+                        gtk::Button {
+                            set_label: "Route from here",
+                            connect_clicked => Msg::RouteFrom,
+                        },
+                        gtk::Button {
+                            set_label: "Route to here",
+                            connect_clicked => Msg::RouteTo,
+                        },
+                    },
+
+                    gtk::Button {
+                        set_label: "Close",
+                        connect_clicked => Msg::Deselect,
+                    },
                 },
             }
         }
@@ -218,50 +1017,166 @@ impl SimpleComponent for App {
 
         let draw_handler = DrawHandler::new();
 
-        let (stars, seed) = generator::generate_stars();
+        let mut map = Map::new(Vec::new(), 0);
+        map.generate(rand::random());
 
+        let seed = map.seed;
         let mut model = App {
-            stars,
-            font_desc: pango::FontDescription::from_string("Monospace Bold 12"),
+            map,
             draw_handler,
-            seed,
-            starcount: 32,
-            jumplines: true,
-            jumpdistance: 10.0,
-            colors: DARK_COLORS.clone(),
-            scale: 50.0,
-            display_class: false,
+            page_setup: PageSetup::default(),
+            image_size: ImageSize::default(),
+            match_window: true,
+            save_format: Format::Png,
+            seed_buffer: gtk::EntryBuffer::new(Some(format!("{seed:#x}"))),
+            shown_seed: seed,
+            hovered: None,
+            selected: None,
+            orrery_handler: DrawHandler::new(),
+            route_from: None,
+            route_to: None,
+            route_cost: RouteCost::Jumps,
+            name_list: None,
+            themes: Vec::new(),
+            theme_names: gtk::StringList::new(&[]),
+            theme_name: String::new(),
+            star_color: (1.0, 1.0, 1.0),
+            edited_class: StarClass::G,
+            overlay_rows: OverlayKind::ALL.map(|kind| OverlayRow::new(kind, &sender)),
         };
+        model.reload_themes();
 
         let _draw_area = model.draw_handler.drawing_area();
+        let _orrery_area = model.orrery_handler.drawing_area();
 
         // Insert the code generation of the view! macro here
         let widgets = view_output!();
 
-		draw::draw(&mut model);
+        for (row, overlay) in (1..).zip(&model.overlay_rows) {
+            let label = gtk::Label::builder().label(overlay.kind.label()).halign(gtk::Align::Start).build();
+            widgets.overlay_grid.attach(&label, 0, row, 1, 1);
+            widgets.overlay_grid.attach(&overlay.visible, 1, row, 1, 1);
+            widgets.overlay_grid.attach(&overlay.corner, 2, row, 1, 1);
+            overlay.update(&model.map.overlays);
+        }
+
+        model.redraw();
 
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
         match msg {
             Msg::FontSelected(desc) => {
                 println!("Font chosen: {:?}", desc.family().unwrap_or("unknown".into()));
-                self.font_desc = desc;
+                self.map.font_desc = desc;
             },
             Msg::StarCountChanged(count) => {
-                self.starcount = count;
+                self.map.set_starcount(count);
             },
             Msg::RegenerateSeed => {
-                let (stars, seed) = generator::generate_stars();
-                self.stars = stars;
-                self.seed = seed;
+                self.map.generate(rand::random());
+                self.forget_stars();
+            },
+            Msg::NameSource(kind) => {
+                let names = match (kind, &self.name_list) {
+                    (0, _) => NameSource::Catalogue,
+                    (1, _) => NameSource::Generated,
+                    (_, Some(list)) => NameSource::Custom(list.clone()),
+                    (_, None) => {
+                        sender.input(Msg::LoadNames);
+                        return;
+                    },
+                };
+                self.set_names(names);
+            },
+            Msg::LoadNames => {
+                let filters = gtk::gio::ListStore::new::<gtk::FileFilter>();
+                let filter = gtk::FileFilter::new();
+                filter.set_name(Some("Name lists"));
+                filter.add_suffix("txt");
+                filter.add_suffix("json");
+                filters.append(&filter);
+                let dialog = gtk::FileDialog::builder()
+                    .title("Load name list")
+                    .filters(&filters)
+                    .build();
+
+                dialog.open(relm4::main_application().active_window().as_ref(), gtk::gio::Cancellable::NONE, move |result| {
+                    match result {
+                        Ok(file) => sender.input(Msg::LoadNamesFrom(file.path().unwrap())),
+                        Err(e) => println!("while picking file: {e:?}"),
+                    }
+                });
+            },
+            Msg::LoadNamesFrom(path) => {
+                match NameList::load(&path) {
+                    Ok(mut list) => {
+                        list.extend = self.name_list.as_ref().is_some_and(|list| list.extend);
+                        self.name_list = Some(list.clone());
+                        self.set_names(NameSource::Custom(list));
+                    },
+                    Err(e) => {
+                        println!("while loading names: {e}");
+                        show_error("Error loading name list", &e);
+                    },
+                }
+            },
+            Msg::ExtendNames(state) => {
+                if let Some(list) = &mut self.name_list {
+                    list.extend = state;
+                    self.set_names(NameSource::Custom(list.clone()));
+                }
+            },
+            Msg::Shape(shape) => {
+                self.map.placement.shape = shape;
+                self.regenerate();
+            },
+            Msg::Arms(arms) => {
+                self.map.placement.arms = arms;
+                self.regenerate();
+            },
+            Msg::Twist(twist) => {
+                self.map.placement.twist = twist;
+                self.regenerate();
+            },
+            Msg::Spread(spread) => {
+                self.map.placement.spread = spread;
+                self.regenerate();
+            },
+            Msg::Flattening(flattening) => {
+                self.map.placement.flattening = flattening;
+                self.regenerate();
+            },
+            Msg::ShapeAngle(angle) => {
+                self.map.placement.angle = angle;
+                self.regenerate();
+            },
+            Msg::Core(core) => {
+                self.map.placement.core = core;
+                self.regenerate();
+            },
+            Msg::Clusters(clusters) => {
+                self.map.placement.clusters = clusters;
+                self.regenerate();
+            },
+            Msg::BandWidth(width) => {
+                self.map.placement.width = width;
+                self.regenerate();
+            },
+            Msg::Rift(rift) => {
+                self.map.placement.rift = rift;
+                self.regenerate();
+            },
+            Msg::Separation(separation) => {
+                self.map.placement.separation = separation;
+                self.regenerate();
             },
             Msg::EditedSeed(newseed) => {
-                match u64::from_str_radix(newseed.strip_prefix("0x").unwrap_or(&newseed), 16) {
+                match generator::parse_seed(&newseed) {
                     Ok(seed) => {
-                        self.seed = seed;
-                        self.stars = generate_stars_with_seed(seed);
+                        self.map.generate(seed);
+                        self.forget_stars();
                     },
                     Err(_) => {
                         let alert = gtk::AlertDialog::builder()
@@ -272,55 +1187,453 @@ impl SimpleComponent for App {
                     },
                 }
             },
-            Msg::LightSelected => {
-                self.colors = LIGHT_COLORS.clone()
+            Msg::Theme(index) => {
+                if let Some(theme) = self.themes.get(index as usize) {
+                    self.map.colors = theme.colors.clone();
+                    self.theme_name = theme.name.clone();
+                    if let Some(color) = theme.colors.starcolor {
+                        self.star_color = color;
+                    }
+                }
+            },
+            Msg::WallColor(color) => {
+                self.map.colors.wall = rgb(&color);
+            },
+            Msg::NameColor(color) => {
+                self.map.colors.starnames = rgb(&color);
+            },
+            Msg::JumplineColor(color) => {
+                self.map.colors.jumplines = rgb(&color);
+            },
+            Msg::UniformStarColor(state) => {
+                self.map.colors.starcolor = state.then_some(self.star_color);
+            },
+            Msg::StarColor(color) => {
+                self.star_color = rgb(&color);
+                if self.map.colors.starcolor.is_some() {
+                    self.map.colors.starcolor = Some(self.star_color);
+                }
+            },
+            Msg::Palette(palette) => {
+                self.map.colors.classes = palette.colors();
             },
-            Msg::DarkSelected => {
-                self.colors = DARK_COLORS.clone()
+            Msg::EditedClass(class) => {
+                self.edited_class = class;
+            },
+            Msg::ClassColor(color) => {
+                self.map.colors.classes.set(self.edited_class, rgb(&color));
+            },
+            Msg::ThemeName(name) => {
+                self.theme_name = name;
+            },
+            Msg::SaveTheme => {
+                match theme::save(&self.theme_name, &self.map.colors) {
+                    Ok(()) => self.reload_themes(),
+                    Err(e) => {
+                        println!("while saving theme: {e}");
+                        show_error("Error saving theme", &e);
+                    },
+                }
+            },
+            Msg::DeleteTheme => {
+                match theme::delete(&self.theme_name) {
+                    Ok(()) => self.reload_themes(),
+                    Err(e) => {
+                        println!("while deleting theme: {e}");
+                        show_error("Error deleting theme", &e);
+                    },
+                }
             },
             Msg::JumpDistance(dist) => {
-                self.jumpdistance = dist;
+                self.map.jumpdistance = dist;
+                self.map.update_jumps();
             },
             Msg::JumpLines(state) => {
-                self.jumplines = state;
+                self.map.jumplines = state;
             },
             Msg::DisplayClass(state) => {
-                self.display_class = state;
+                self.map.display_class = state;
+            },
+            Msg::SpectralType(state) => {
+                self.map.spectral_type = state;
+            },
+            Msg::Glow(state) => {
+                self.map.glow = state;
+            },
+            Msg::Monochrome(state) => {
+                self.map.monochrome = state;
+                // the glyphs don't explain themselves
+                if state {
+                    self.map.overlays.legend.visible = true;
+                }
+            },
+            Msg::MapTitle(title) => {
+                self.map.title = title;
+            },
+            Msg::ShowOverlay(kind, state) => {
+                self.map.overlays.get_mut(kind).visible = state;
+            },
+            Msg::OverlayCorner(kind, corner) => {
+                self.map.overlays.get_mut(kind).corner = corner;
+            },
+            Msg::MatchWindow(state) => {
+                self.match_window = state;
+            },
+            Msg::ImageWidth(width) => {
+                self.image_size.width = width;
+            },
+            Msg::ImageHeight(height) => {
+                self.image_size.height = height;
+            },
+            Msg::Dpi(dpi) => {
+                self.image_size.dpi = dpi;
+            },
+            Msg::FitToPaper => {
+                self.image_size = ImageSize::for_paper(&self.page_setup, self.image_size.dpi);
+            },
+            Msg::Paper(paper) => {
+                self.page_setup.paper = paper;
+            },
+            Msg::Landscape(state) => {
+                self.page_setup.landscape = state;
+            },
+            Msg::Margin(margin) => {
+                self.page_setup.margin = margin;
+            },
+            Msg::Bleed(bleed) => {
+                self.page_setup.bleed = bleed;
+            },
+            Msg::Columns(columns) => {
+                self.page_setup.columns = columns;
+            },
+            Msg::Rows(rows) => {
+                self.page_setup.rows = rows;
+            },
+            Msg::Overlap(overlap) => {
+                self.page_setup.overlap = overlap;
             },
             Msg::Save => {
-                let surface = self.draw_handler.get_context().target().clone();
+                let filters = export::file_filters();
+                let offered = self.save_format;
                 let dialog = gtk::FileDialog::builder()
                     .title("Save starmap")
-                    .initial_name("starmap.png")
+                    .initial_name(format!("starmap.{}", offered.suffix()))
+                    .filters(&filters)
+                    .default_filter(&filters.item(offered as u32).and_downcast::<gtk::FileFilter>().unwrap())
                     .build();
 
+                let picked = dialog.clone();
                 dialog.save(relm4::main_application().active_window().as_ref(), gtk::gio::Cancellable::NONE, move |result| {
                     match result {
-                        Ok(file) => match File::create(file.path().unwrap()) {
-                            Ok(mut file) => match surface.write_to_png(&mut file) {
-                                Ok(()) => {},
-                                Err(e) => println!("while writing: {e:?}"),
-                            },
-                            Err(e) => {
-                                println!("while creating file: {e:?}");
-                                let alert = gtk::AlertDialog::builder()
-                                    .message("Error creating file")
-                                    .detail(format!("Error: {}", e))
-                                    .build();
-                                alert.show(relm4::main_application().active_window().as_ref());
-                            },
+                        Ok(file) => {
+                            let path = file.path().unwrap();
+                            // the dialog keeps the filter that was picked as its default, a filter the
+                            // user switched to wins over the suffix, otherwise it only fills in a missing one
+                            let path = match picked.default_filter().as_ref().and_then(Format::from_filter) {
+                                Some(format) if format != offered || path.extension().is_none() => format.with_suffix(&path),
+                                _ => path,
+                            };
+                            sender.input(Msg::SaveTo(path))
                         },
                         Err(e) => println!("while picking file: {e:?}"),
                     }
                 });
             },
+            Msg::SaveTo(path) => {
+                let size = match self.match_window {
+                    true => ImageSize {
+                        width: self.draw_handler.width() as u32,
+                        height: self.draw_handler.height() as u32,
+                        dpi: 96.0,
+                    },
+                    false => self.image_size.clone(),
+                };
+                match export::export(&path, &size, &self.map, &self.page_setup) {
+                    Ok(()) => self.save_format = Format::from_path(&path).unwrap_or(Format::Png),
+                    Err(e) => {
+                        println!("while saving: {e}");
+                        show_error("Error saving starmap", &e);
+                    },
+                }
+            },
+            Msg::ExportCatalogue => {
+                let dialog = gtk::FileDialog::builder()
+                    .title("Export star catalogue")
+                    .initial_name("stars.csv")
+                    .filters(&export::catalogue::file_filters())
+                    .build();
+
+                dialog.save(relm4::main_application().active_window().as_ref(), gtk::gio::Cancellable::NONE, move |result| {
+                    match result {
+                        Ok(file) => sender.input(Msg::ExportCatalogueTo(file.path().unwrap())),
+                        Err(e) => println!("while picking file: {e:?}"),
+                    }
+                });
+            },
+            Msg::ExportCatalogueTo(path) => {
+                if let Err(e) = export::catalogue::write_catalogue(&path, &self.map) {
+                    println!("while exporting catalogue: {e}");
+                    show_error("Error exporting catalogue", &e);
+                }
+            },
+            Msg::OpenProject => {
+                let dialog = gtk::FileDialog::builder()
+                    .title("Open project")
+                    .filters(&project::file_filters())
+                    .build();
+
+                dialog.open(relm4::main_application().active_window().as_ref(), gtk::gio::Cancellable::NONE, move |result| {
+                    match result {
+                        Ok(file) => sender.input(Msg::OpenProjectFrom(file.path().unwrap())),
+                        Err(e) => println!("while picking file: {e:?}"),
+                    }
+                });
+            },
+            Msg::OpenProjectFrom(path) => {
+                match project::open(&path) {
+                    Ok(map) => {
+                        if let NameSource::Custom(list) = &map.names {
+                            self.name_list = Some(list.clone());
+                        }
+                        self.map = map;
+                        self.forget_stars();
+                    },
+                    Err(e) => {
+                        println!("while opening project: {e}");
+                        show_error("Error opening project", &e);
+                    },
+                }
+            },
+            Msg::SaveProject => {
+                let dialog = gtk::FileDialog::builder()
+                    .title("Save project")
+                    .initial_name(format!("starmap.{}", project::EXTENSION))
+                    .filters(&project::file_filters())
+                    .build();
+
+                dialog.save(relm4::main_application().active_window().as_ref(), gtk::gio::Cancellable::NONE, move |result| {
+                    match result {
+                        Ok(file) => sender.input(Msg::SaveProjectTo(file.path().unwrap())),
+                        Err(e) => println!("while picking file: {e:?}"),
+                    }
+                });
+            },
+            Msg::SaveProjectTo(path) => {
+                if let Err(e) = project::save(&path, &self.map) {
+                    println!("while saving project: {e}");
+                    show_error("Error saving project", &e);
+                }
+            },
             Msg::Resize(_w, _h) => {},
+            Msg::Hover(position) => {
+                let (width, height) = (self.draw_handler.width() as f64, self.draw_handler.height() as f64);
+                self.hovered = position.and_then(|(x, y)| self.map.star_at(x, y, width, height));
+                // nothing on the map changes
+                return;
+            },
+            Msg::Click(x, y) => {
+                let (width, height) = (self.draw_handler.width() as f64, self.draw_handler.height() as f64);
+                self.selected = self.map.star_at(x, y, width, height);
+            },
+            Msg::Deselect => {
+                self.selected = None;
+            },
+            Msg::RouteFrom => {
+                self.route_from = self.selected;
+            },
+            Msg::RouteTo => {
+                self.route_to = self.selected;
+            },
+            Msg::RouteCost(cost) => {
+                self.route_cost = cost;
+            },
+            Msg::ClearRoute => {
+                self.route_from = None;
+                self.route_to = None;
+            },
+        }
+        for row in &self.overlay_rows {
+            row.update(&self.map.overlays);
+        }
+        if self.map.seed != self.shown_seed {
+            self.shown_seed = self.map.seed;
+            self.seed_buffer.set_text(format!("{:#x}", self.map.seed));
+        }
+        self.update_route();
+        self.redraw();
+    }
+}
+
+fn rgba(color: (f64,f64,f64)) -> gdk::RGBA {
+    gdk::RGBA::new(color.0 as f32, color.1 as f32, color.2 as f32, 1.0)
+}
+
+fn rgb(color: &gdk::RGBA) -> (f64,f64,f64) {
+    (color.red() as f64, color.green() as f64, color.blue() as f64)
+}
+
+fn show_error(message: &str, detail: &str) {
+    let alert = gtk::AlertDialog::builder()
+        .message(message)
+        .detail(format!("Error: {}", detail))
+        .build();
+    alert.show(relm4::main_application().active_window().as_ref());
+}
+
+impl App {
+    fn redraw(&mut self) {
+        let (width, height) = (self.draw_handler.width() as f64, self.draw_handler.height() as f64);
+        let cx = self.draw_handler.get_context();
+        draw::draw(&cx, width, height, &self.map);
+
+        // not selected_star(), that would borrow the orrery handler as well
+        if let Some(star) = self.selected.and_then(|i| self.map.visible_stars().get(i)) {
+            draw::draw_selection(&cx, width, height, star, &self.map);
+
+            let cx = self.orrery_handler.get_context();
+            let (width, height) = (self.orrery_handler.width() as f64, self.orrery_handler.height() as f64);
+            draw::draw_orrery(&cx, width, height, star, &self.map);
+        }
+    }
+
+    /// Regenerates the stars of the current seed with other names.
+    fn set_names(&mut self, names: NameSource) {
+        self.map.names = names;
+        self.regenerate();
+    }
+
+    /// Regenerates the stars of the current seed, for when what they are generated from changed.
+    fn regenerate(&mut self) {
+        self.map.generate(self.map.seed);
+        self.forget_stars();
+    }
+
+    /// Reads the themes again, for when one was saved or deleted.
+    fn reload_themes(&mut self) {
+        self.themes = theme::load_all();
+        let names: Vec<&str> = self.themes.iter().map(|theme| theme.name.as_str()).collect();
+        self.theme_names.splice(0, self.theme_names.n_items(), &names);
+    }
+
+    /// The theme the map has the colors of, preferring the one named in the editor.
+    fn theme_index(&self) -> u32 {
+        let matching = |theme: &Theme| theme.colors == self.map.colors;
+        self.themes.iter().position(|theme| matching(theme) && theme.name.eq_ignore_ascii_case(self.theme_name.trim()))
+            .or_else(|| self.themes.iter().position(matching))
+            .map_or(gtk::INVALID_LIST_POSITION, |index| index as u32)
+    }
+
+    fn name_list_label(&self) -> String {
+        match &self.name_list {
+            Some(list) => format!("{} ({} names)", list.file, list.names.len()),
+            None => "Load name list".into(),
+        }
+    }
+
+    /// Drops everything that refers to stars by index, for when the stars are replaced.
+    fn forget_stars(&mut self) {
+        self.selected = None;
+        self.route_from = None;
+        self.route_to = None;
+    }
+
+    fn update_route(&mut self) {
+        self.map.route = match (self.route_from, self.route_to) {
+            (Some(from), Some(to)) => self.map.jumps.route(self.map.visible_stars(), from, to, self.route_cost).unwrap_or_default(),
+            _ => Vec::new(),
+        };
+    }
+
+    fn route_description(&self) -> String {
+        let stars = self.map.visible_stars();
+        let name = |star: Option<usize>| star.and_then(|i| stars.get(i)).map(|star| star.name.as_str());
+        let (from, to) = match (name(self.route_from), name(self.route_to)) {
+            (Some(from), Some(to)) => (from, to),
+            (Some(from), None) => return format!("From {from}, pick a destination on the map."),
+            (None, Some(to)) => return format!("To {to}, pick a start on the map."),
+            (None, None) => return "Click a star to start a route from or to it.".into(),
+        };
+        if self.map.route.is_empty() {
+            return format!("There is no route from {from} to {to} with jumps of up to {} ly.", self.map.jumpdistance);
+        }
+
+        let mut steps = String::new();
+        let mut total = 0.0;
+        for pair in self.map.route.windows(2) {
+            let distance = stars[pair[0]].distance(&stars[pair[1]]) * self.map.scale;
+            total += distance;
+            steps.push_str(&format!("\n  {} ({distance:.1} ly)", stars[pair[1]].name));
         }
-        draw::draw(self);
+        format!("{from} → {to}\n{} jumps, {total:.1} ly\n  {from}{steps}", self.map.route.len() - 1)
     }
+
+    fn selected_star(&self) -> Option<&Star> {
+        self.map.visible_stars().get(self.selected?)
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let star = self.map.visible_stars().get(self.hovered?)?;
+        Some(format!("{} [{}]\n{}", star.system_name(), star.spectral_type(), planet_list(star)))
+    }
+
+    fn star_details(&self) -> String {
+        let Some(star) = self.selected_star() else {
+            return String::new();
+        };
+        let (r, g, b) = self.map.colors.classes.get(star.class);
+        let properties = &star.properties;
+        format!(
+            "<big><b>{}</b></big>\n<span foreground=\"#{:02x}{:02x}{:02x}\">⬤</span> {}, {}\n\
+            {:.2} M☉, {:.2} R☉, {:.0} K\n{:.3} L☉, {:.2} billion years old{}",
+            gtk::glib::markup_escape_text(&star.system_name()),
+            (r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8,
+            star.spectral_type(),
+            match star.class.is_ordinary() {
+                true => properties.luminosity_class.description(),
+                false => star.class.name(),
+            },
+            properties.mass,
+            properties.radius,
+            properties.temperature,
+            properties.luminosity,
+            properties.age,
+            companion_list(star),
+        )
+    }
+}
+
+/// The other stars of the system, lettered after the main star.
+fn companion_list(star: &Star) -> String {
+    let mut text = String::new();
+    for (i, companion) in star.companions.iter().enumerate() {
+        let letter = (b'B' + i as u8) as char;
+        text.push_str(&format!("\n{letter}: {} at {:.1} AU", companion.spectral_type(), companion.separation));
+    }
+    text
+}
+
+fn planet_list(star: &Star) -> String {
+    let mut text = match star.planets.len() {
+        0 => "no planets".to_string(),
+        1 => "1 planet".to_string(),
+        n => format!("{n} planets"),
+    };
+    for planet in &star.planets {
+        text.push_str(&format!("\n  {planet}"));
+    }
+    text
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).is_some_and(|cmd| cmd == "render") {
+        if let Err(e) = cli::render(&args[2..]) {
+            eprintln!("starmap: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let app = RelmApp::new("ng.levitati.Starmap");
     app.run::<App>(());
 }
@@ -0,0 +1,193 @@
+use gtk::pango;
+use serde::{Deserialize, Serialize};
+
+use crate::draw::Overlays;
+use crate::generator::{names::NameSource, placement::Placement, Star, StarGenerator};
+use crate::jumps::JumpGraph;
+use crate::palette::{ClassColors, Palette};
+
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Colors {
+    pub starnames: (f64,f64,f64),
+    pub wall: (f64,f64,f64),
+    pub starcolor: Option<(f64,f64,f64)>,
+    pub jumplines: (f64,f64,f64),
+    /// Colors of the star classes, when there is no `starcolor`
+    #[serde(default)]
+    pub classes: ClassColors,
+}
+
+pub static DARK_COLORS: Colors = Colors {
+    starnames: (1.0,1.0,1.0),
+    wall: (0.0,0.0,0.0),
+    starcolor: None,
+    jumplines: (0.5,0.5,0.5),
+    classes: Palette::Realistic.colors(),
+};
+
+pub static LIGHT_COLORS: Colors = Colors {
+    starnames: (0.0,0.0,0.0),
+    wall: (1.0,1.0,1.0),
+    starcolor: None,
+    jumplines: (0.7,0.7,0.7),
+    classes: Palette::Realistic.colors(),
+};
+
+/// Everything that determines how a map looks, independent of where it is drawn.
+#[derive(Serialize, Deserialize)]
+pub struct Map {
+    pub stars: Vec<Star>,
+    /// Shown in the title overlay
+    #[serde(default)]
+    pub title: String,
+    #[serde(rename = "font", with = "font")]
+    pub font_desc: pango::FontDescription,
+    pub starcount: u32,
+    pub seed: u64,
+    #[serde(default)]
+    pub names: NameSource,
+    #[serde(default)]
+    pub placement: Placement,
+    pub jumplines: bool,
+    pub jumpdistance: f64,
+    pub colors: Colors,
+    pub scale: f64,
+    pub display_class: bool,
+    /// Show the full spectral type, like G2V, instead of just the class
+    #[serde(default)]
+    pub spectral_type: bool,
+    /// Draw a glow around bright stars
+    #[serde(default)]
+    pub glow: bool,
+    /// Tell classes apart by glyph instead of color, for black and white printers
+    #[serde(default)]
+    pub monochrome: bool,
+    #[serde(default)]
+    pub overlays: Overlays,
+    /// Kept up to date by [`Map::update_jumps`]
+    #[serde(skip)]
+    pub jumps: JumpGraph,
+    /// Indices of the stars along a highlighted route
+    #[serde(skip)]
+    pub route: Vec<usize>,
+    /// Picks up where `stars` ends, so adding stars doesn't generate the earlier ones again
+    #[serde(skip)]
+    generator: Option<StarGenerator>,
+}
+
+impl Map {
+    pub fn new(stars: Vec<Star>, seed: u64) -> Self {
+        let mut map = Map {
+            stars,
+            title: String::new(),
+            font_desc: pango::FontDescription::from_string("Monospace Bold 12"),
+            seed,
+            names: NameSource::default(),
+            placement: Placement::default(),
+            starcount: 32,
+            jumplines: true,
+            jumpdistance: 10.0,
+            colors: DARK_COLORS.clone(),
+            scale: 50.0,
+            display_class: false,
+            spectral_type: false,
+            glow: false,
+            monochrome: false,
+            overlays: Overlays::default(),
+            jumps: JumpGraph::default(),
+            route: Vec::new(),
+            generator: None,
+        };
+        map.update_jumps();
+        map
+    }
+
+    /// Replaces the stars with new ones generated from `seed`.
+    pub fn generate(&mut self, seed: u64) {
+        self.stars.clear();
+        self.seed = seed;
+        self.set_starcount(self.starcount);
+    }
+
+    /// Shows `count` stars, generating more if the map doesn't have that many yet.
+    pub fn set_starcount(&mut self, count: u32) {
+        let have = self.stars.len();
+        if count as usize > have {
+            let generator = match self.generator.take() {
+                Some(generator) if generator.continues(have, self.seed, &self.names, self.placement, self.scale) => generator,
+                // the seed, names or placement changed, or the stars came from a project
+                _ => {
+                    let mut generator = StarGenerator::new(self.seed, &self.names, self.placement, self.scale);
+                    generator.by_ref().take(have).for_each(drop);
+                    generator
+                },
+            };
+            let generator = self.generator.insert(generator);
+            self.stars.extend(generator.take(count as usize - have));
+        }
+        self.starcount = count;
+        self.update_jumps();
+    }
+
+    /// Recomputes the jumplines, needed whenever the visible stars, the scale or the jump distance change.
+    pub fn update_jumps(&mut self) {
+        self.jumps = JumpGraph::new(self.visible_stars(), self.jumpdistance / self.scale);
+    }
+
+    /// How many of the `starcount` stars found no room on the map, because of the separation.
+    pub fn missing_stars(&self) -> usize {
+        (self.starcount as usize).saturating_sub(self.stars.len())
+    }
+
+    /// The first `starcount` stars, there can be more generated than shown.
+    pub fn visible_stars(&self) -> &[Star] {
+        &self.stars[0..(self.starcount as usize).min(self.stars.len())]
+    }
+
+    /// Index of the visible star closest to `(x, y)` on a map drawn at `width` by `height`,
+    /// if there is one within a few pixels.
+    pub fn star_at(&self, x: f64, y: f64, width: f64, height: f64) -> Option<usize> {
+        self.visible_stars().iter().enumerate()
+            .map(|(i, star)| (i, (star.cords.0 * width - x).hypot(star.cords.1 * height - y)))
+            .filter(|&(_, distance)| distance < 8.0)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    /// Indices of the visible stars within jump distance of the star at `index`.
+    pub fn jump_neighbours(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.jumps.neighbours(index).iter().copied()
+    }
+}
+
+/// Stores fonts the way the font chooser shows them, e.g. "Monospace Bold 12".
+mod font {
+    use gtk::pango::FontDescription;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(font: &FontDescription, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&font.to_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<FontDescription, D::Error> {
+        Ok(FontDescription::from_string(&String::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn more_stars_keep_the_first_ones() {
+        let mut map = Map::new(Vec::new(), 0);
+        map.starcount = 10;
+        map.generate(0x5eed);
+        let first: Vec<(String, (f64, f64))> = map.stars.iter().map(|star| (star.name.clone(), star.cords)).collect();
+
+        map.set_starcount(40);
+        assert_eq!(map.stars.len(), 40);
+        let kept: Vec<(String, (f64, f64))> = map.stars[..10].iter().map(|star| (star.name.clone(), star.cords)).collect();
+        assert_eq!(first, kept);
+    }
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::generator::classes::StarClass;
+
+type Rgb = (f64,f64,f64);
+
+/// The color of every star class, in the order of [`StarClass::ALL`].
+/// Stored as a map from class code to color.
+#[derive(PartialEq, Clone, Debug)]
+pub struct ClassColors(pub [Rgb; StarClass::ALL.len()]);
+
+impl ClassColors {
+    pub fn get(&self, class: StarClass) -> Rgb {
+        self.0[class as usize]
+    }
+
+    pub fn set(&mut self, class: StarClass, color: Rgb) {
+        self.0[class as usize] = color;
+    }
+}
+
+impl Default for ClassColors {
+    fn default() -> Self {
+        Palette::Realistic.colors()
+    }
+}
+
+impl Serialize for ClassColors {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(StarClass::ALL.iter().zip(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for ClassColors {
+    /// Classes that are missing keep their realistic color.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let map = HashMap::<StarClass, Rgb>::deserialize(deserializer)?;
+        let mut colors = ClassColors::default();
+        for (class, color) in map {
+            colors.set(class, color);
+        }
+        Ok(colors)
+    }
+}
+
+/// Ready-made class colors to start a theme from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Palette {
+    /// Roughly the color the stars would have to the eye
+    Realistic,
+    HighContrast,
+    /// Runs from blue to orange, which stays apart for red-green color blindness
+    ColorblindSafe,
+    /// Shades of gray that still print well on white paper, hotter classes are darker
+    Grayscale,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [Palette::Realistic, Palette::HighContrast, Palette::ColorblindSafe, Palette::Grayscale];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Realistic => "realistic",
+            Palette::HighContrast => "high-contrast",
+            Palette::ColorblindSafe => "colorblind-safe",
+            Palette::Grayscale => "grayscale",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Palette> {
+        Palette::ALL.into_iter().find(|palette| palette.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The palette that has exactly these colors, if any.
+    pub fn of(colors: &ClassColors) -> Option<Palette> {
+        Palette::ALL.into_iter().find(|palette| palette.colors() == *colors)
+    }
+
+    /*
+    def hex_to_rgb(hex_color)
+        r = hex_color[0..1].to_i(16) / 255.0
+        g = hex_color[2..3].to_i(16) / 255.0
+        b = hex_color[4..5].to_i(16) / 255.0
+        [r.round(2), g.round(2), b.round(2)]
+    end
+    */
+
+    // in the order of StarClass::ALL: O B A F G K M L T Y D NS BH WR
+    pub const fn colors(self) -> ClassColors {
+        ClassColors(match self {
+            Palette::Realistic => [
+                (0.61, 0.69, 1.0),
+                (0.64, 0.75, 1.0),
+                (0.84, 0.88, 1.0),
+                (0.98, 0.96, 1.0),
+                (1.0, 0.93, 0.89),
+                (1.0, 0.85, 0.71),
+                (1.0, 0.71, 0.42),
+                (0.9, 0.4, 0.25),
+                (0.7, 0.3, 0.45),
+                (0.5, 0.25, 0.4),
+                (0.9, 0.95, 1.0),
+                (0.6, 0.85, 1.0),
+                (0.7, 0.45, 0.9),
+                (0.5, 0.6, 1.0),
+            ],
+            Palette::HighContrast => [
+                (0.2, 0.4, 1.0),
+                (0.3, 0.7, 1.0),
+                (0.6, 0.95, 1.0),
+                (1.0, 1.0, 1.0),
+                (1.0, 1.0, 0.0),
+                (1.0, 0.6, 0.0),
+                (1.0, 0.2, 0.1),
+                (0.85, 0.0, 0.45),
+                (0.6, 0.0, 0.8),
+                (0.45, 0.25, 0.65),
+                (0.8, 1.0, 1.0),
+                (0.0, 1.0, 0.6),
+                (0.8, 0.2, 1.0),
+                (0.0, 0.3, 1.0),
+            ],
+            // mostly the Okabe-Ito colors
+            Palette::ColorblindSafe => [
+                (0.0, 0.45, 0.7),
+                (0.34, 0.71, 0.91),
+                (0.7, 0.85, 0.95),
+                (1.0, 1.0, 1.0),
+                (0.94, 0.89, 0.26),
+                (0.9, 0.6, 0.0),
+                (0.84, 0.37, 0.0),
+                (0.65, 0.4, 0.2),
+                (0.5, 0.3, 0.15),
+                (0.35, 0.2, 0.1),
+                (0.85, 0.85, 0.85),
+                (0.0, 0.62, 0.45),
+                (0.8, 0.47, 0.65),
+                (0.1, 0.3, 0.9),
+            ],
+            Palette::Grayscale => [
+                (0.0, 0.0, 0.0),
+                (0.08, 0.08, 0.08),
+                (0.16, 0.16, 0.16),
+                (0.24, 0.24, 0.24),
+                (0.32, 0.32, 0.32),
+                (0.4, 0.4, 0.4),
+                (0.48, 0.48, 0.48),
+                (0.56, 0.56, 0.56),
+                (0.62, 0.62, 0.62),
+                (0.68, 0.68, 0.68),
+                (0.2, 0.2, 0.2),
+                (0.1, 0.1, 0.1),
+                (0.0, 0.0, 0.0),
+                (0.0, 0.0, 0.0),
+            ],
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::generator;
+use crate::map::Map;
+
+/// Bumped whenever a project can no longer be read by older versions.
+///
+/// 2: stars have planets, physical properties, companions and classes with more than one letter.
+/// Version 1 projects may lack any of those, [`migrate`] fills them in.
+pub const VERSION: u32 = 2;
+
+pub const EXTENSION: &str = "starmap";
+
+#[derive(Serialize)]
+struct Project<'a> {
+    version: u32,
+    #[serde(flatten)]
+    map: &'a Map,
+}
+
+pub fn file_filters() -> gtk::gio::ListStore {
+    let filters = gtk::gio::ListStore::new::<gtk::FileFilter>();
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some("Starmap project"));
+    filter.add_suffix(EXTENSION);
+    filters.append(&filter);
+    filters
+}
+
+pub fn save(path: &Path, map: &Map) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&Project { version: VERSION, map })
+        .map_err(|e| format!("while serializing: {e}"))?;
+    std::fs::write(path, json).map_err(|e| format!("while writing {}: {e}", path.display()))
+}
+
+pub fn open(path: &Path) -> Result<Map, String> {
+    let json = std::fs::read_to_string(path).map_err(|e| format!("while reading {}: {e}", path.display()))?;
+
+    #[derive(Deserialize)]
+    struct Version {
+        version: u32,
+    }
+    let project: serde_json::Value = serde_json::from_str(&json).map_err(|e| format!("not a starmap project: {e}"))?;
+    let Version { version } = Version::deserialize(&project).map_err(|e| format!("not a starmap project: {e}"))?;
+    if version > VERSION {
+        return Err(format!("the project was made with a newer version of starmap (format {version})"));
+    }
+
+    let mut map = Map::deserialize(&project).map_err(|e| format!("invalid project: {e}"))?;
+    if version < 2 {
+        migrate(&mut map, &project);
+    }
+    // monochrome maps saved before there were overlays need the legend to be read
+    if map.monochrome && project.get("overlays").is_none() {
+        map.overlays.legend.visible = true;
+    }
+    // generates whatever stars are missing, and the jumps that aren't stored
+    map.set_starcount(map.starcount);
+    Ok(map)
+}
+
+/// Fills in what stars of a version 1 project weren't saved with, the way the seed generates it now.
+/// `project` is the project as it was read, to tell missing fields from empty ones.
+fn migrate(map: &mut Map, project: &serde_json::Value) {
+    let saved = project["stars"].as_array().map(Vec::as_slice).unwrap_or_default();
+    for (i, (star, saved)) in map.stars.iter_mut().zip(saved).enumerate() {
+        if saved.get("properties").is_none() {
+            star.properties = generator::star_properties(map.seed, i, star.class);
+        }
+        // before planets were generated only their number was saved
+        if saved.get("planets").is_none() {
+            let count = saved["_planets"].as_u64().unwrap_or(0).min(u8::MAX as u64) as u8;
+            star.planets = generator::star_planets(map.seed, i, count, star.properties.luminosity);
+        }
+        if saved.get("companions").is_none() {
+            star.companions = generator::star_companions(map.seed, i, star.class, &star.properties);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use crate::map::{Colors, DARK_COLORS, LIGHT_COLORS};
+
+/// A set of colors under a name. Saved themes are kept as JSON files in [`theme_dir`],
+/// named after the theme.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub name: String,
+    pub colors: Colors,
+    /// Dark and Light come with starmap and can't be overwritten or deleted
+    pub builtin: bool,
+}
+
+/// Where saved themes live, e.g. ~/.config/starmap/themes
+pub fn theme_dir() -> PathBuf {
+    gtk::glib::user_config_dir().join("starmap").join("themes")
+}
+
+fn builtin() -> Vec<Theme> {
+    vec![
+        Theme { name: "Dark".into(), colors: DARK_COLORS.clone(), builtin: true },
+        Theme { name: "Light".into(), colors: LIGHT_COLORS.clone(), builtin: true },
+    ]
+}
+
+/// The built-in themes followed by the saved ones, sorted by name.
+/// Files that aren't themes are skipped.
+pub fn load_all() -> Vec<Theme> {
+    let mut saved: Vec<Theme> = std::fs::read_dir(theme_dir()).into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| match load(&entry.path()) {
+            Ok(theme) => theme,
+            Err(e) => {
+                println!("while loading themes: {e}");
+                None
+            },
+        })
+        .collect();
+    saved.sort_by_key(|theme| theme.name.to_lowercase());
+
+    let mut themes = builtin();
+    themes.extend(saved.into_iter().filter(|theme| find(&builtin(), &theme.name).is_none()));
+    themes
+}
+
+fn load(path: &Path) -> Result<Option<Theme>, String> {
+    if path.extension().is_none_or(|ext| ext != "json") {
+        return Ok(None);
+    }
+    let Some(name) = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()) else {
+        return Ok(None);
+    };
+    let json = std::fs::read_to_string(path).map_err(|e| format!("while reading {}: {e}", path.display()))?;
+    let colors = serde_json::from_str(&json).map_err(|e| format!("invalid theme {}: {e}", path.display()))?;
+    Ok(Some(Theme { name, colors, builtin: false }))
+}
+
+/// The theme called `name`, ignoring case.
+pub fn find<'a>(themes: &'a [Theme], name: &str) -> Option<&'a Theme> {
+    themes.iter().find(|theme| theme.name.eq_ignore_ascii_case(name))
+}
+
+/// Saves `colors` as the theme `name`, replacing a saved theme of the same name.
+pub fn save(name: &str, colors: &Colors) -> Result<(), String> {
+    let path = theme_path(name)?;
+    let json = serde_json::to_string_pretty(colors).map_err(|e| format!("while serializing: {e}"))?;
+    std::fs::create_dir_all(theme_dir()).map_err(|e| format!("while creating {}: {e}", theme_dir().display()))?;
+    std::fs::write(&path, json).map_err(|e| format!("while writing {}: {e}", path.display()))
+}
+
+pub fn delete(name: &str) -> Result<(), String> {
+    let path = theme_path(name)?;
+    std::fs::remove_file(&path).map_err(|e| format!("while deleting {}: {e}", path.display()))
+}
+
+/// The file of a saved theme, the name has to be usable as a file name.
+/// A theme that is already saved keeps its file, even if `name` is written in another case.
+fn theme_path(name: &str) -> Result<PathBuf, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("the theme needs a name".into());
+    }
+    if name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(format!("{name} can't be used as a theme name"));
+    }
+    if find(&builtin(), name).is_some() {
+        return Err(format!("{name} is a built-in theme, pick another name"));
+    }
+    let themes = load_all();
+    let name = find(&themes, name).map_or(name, |theme| &theme.name);
+    Ok(theme_dir().join(format!("{name}.json")))
+}